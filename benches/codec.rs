@@ -0,0 +1,129 @@
+use std::{hint::black_box, net::Ipv4Addr};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use tokio_util::codec::Decoder;
+
+use fastcgi::{
+    codec::FastCgiCodec,
+    record::{ByteSlice, NameValuePair, NameValuePairs, Params, Stdin},
+    request::{Request, Responder},
+};
+
+const STDOUT_SIZE: usize = 1024 * 1024;
+const MAX_PAYLOAD_SIZE: usize = u16::MAX as usize;
+
+fn request(stdin: &Bytes) -> Request {
+    let params = Params::builder::<Responder>()
+        .server_port(80)
+        .server_addr(Ipv4Addr::LOCALHOST.into());
+
+    let stdin = Stdin(ByteSlice::new(stdin.clone()).unwrap());
+
+    Request::builder().params(params).stdin(stdin).build()
+}
+
+fn put_frame(record_type: u8, payload: &[u8], dst: &mut BytesMut) {
+    dst.put_slice(&[1, record_type, 0, 1]);
+    dst.put_u16(payload.len() as u16);
+    dst.put_slice(&[0, 0]);
+    dst.put_slice(payload);
+}
+
+/// A response with a stdout stream of `STDOUT_SIZE` bytes, split into frames of maximum size.
+fn response() -> BytesMut {
+    let mut dst = BytesMut::new();
+
+    let stdout = vec![b'a'; STDOUT_SIZE];
+    for chunk in stdout.chunks(MAX_PAYLOAD_SIZE) {
+        put_frame(6, chunk, &mut dst);
+    }
+    put_frame(6, &[], &mut dst);
+    put_frame(3, &[0, 0, 0, 0, 0, 0, 0, 0], &mut dst);
+
+    dst
+}
+
+fn name_value_pairs(n: usize) -> NameValuePairs {
+    (0..n)
+        .map(|i| NameValuePair::new(format!("PARAM_{i}"), "value".repeat(i % 32 + 1)).unwrap())
+        .collect()
+}
+
+fn request_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("request_encode");
+
+    for (name, size) in [("stdin_16kb", 16 * 1024), ("stdin_1mb", 1024 * 1024)] {
+        // Cloning the stdin only increments a reference count.
+        let stdin = Bytes::from(vec![b'a'; size]);
+
+        // Also decodes the frames, as `into_frames` is the public entry point to the encoder.
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || request(&stdin),
+                |request| black_box(request.into_frames().unwrap()),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn response_decode(c: &mut Criterion) {
+    let src = response();
+
+    let mut group = c.benchmark_group("response_decode");
+    group.throughput(Throughput::Bytes(STDOUT_SIZE as u64));
+    group.bench_function("stdout_1mb", |b| {
+        b.iter_batched(
+            || (FastCgiCodec::new(), src.clone()),
+            |(mut codec, mut src)| {
+                while let Some(frame) = codec.decode(&mut src).unwrap() {
+                    black_box(frame);
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn nvps(c: &mut Criterion) {
+    // A typical request has around 15 params.
+    for n in [15, 100] {
+        nvps_n(c, n);
+    }
+}
+
+fn nvps_n(c: &mut Criterion, n: usize) {
+    let nvps = name_value_pairs(n);
+
+    let mut encoded = BytesMut::new();
+    nvps.clone().encode_chunk(&mut encoded).unwrap().unwrap();
+
+    let mut group = c.benchmark_group(format!("nvps_{n}"));
+    group.throughput(Throughput::Bytes(encoded.len() as u64));
+    group.bench_function("encode", |b| {
+        b.iter_batched(
+            || (nvps.clone(), BytesMut::with_capacity(encoded.len())),
+            |(mut nvps, mut dst)| {
+                nvps.encode_chunk(&mut dst).unwrap().unwrap();
+                black_box(dst)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("decode", |b| {
+        b.iter_batched(
+            || encoded.clone(),
+            |src| black_box(NameValuePairs::decode(src, Params::validate).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, request_encode, response_decode, nvps);
+criterion_main!(benches);
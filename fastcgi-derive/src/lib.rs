@@ -0,0 +1,127 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, GenericArgument, LitStr,
+    PathArguments, Type,
+};
+
+/// Derives `fastcgi::record::FromParams` for a struct with named fields.
+///
+/// Every field requires a `#[param("NAME")]` attribute, and is parsed from the value of that
+/// param with `FromStr`. Fields of type `Option<T>` are optional, all other fields are required.
+///
+/// ```ignore
+/// #[derive(FromParams)]
+/// struct CgiVars {
+///     #[param("REQUEST_METHOD")]
+///     method: String,
+///     #[param("SERVER_PORT")]
+///     port: u16,
+///     #[param("REMOTE_ADDR")]
+///     addr: Option<IpAddr>,
+/// }
+/// ```
+#[proc_macro_derive(FromParams, attributes(param))]
+pub fn derive_from_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.span(),
+            "FromParams can only be derived for structs",
+        ));
+    };
+
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(
+            data.fields.span(),
+            "FromParams requires named fields",
+        ));
+    };
+
+    let fields = fields
+        .named
+        .iter()
+        .map(|field| {
+            let ident = &field.ident;
+            let name = param_name(field)?;
+
+            let value = if option_inner(&field.ty).is_some() {
+                quote! { params.parse_param(#name)? }
+            } else {
+                quote! {
+                    params
+                        .parse_param(#name)?
+                        .ok_or(::fastcgi::record::FromParamsError::MissingParam(#name))?
+                }
+            };
+
+            Ok(quote! { #ident: #value })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::fastcgi::record::FromParams for #ident #ty_generics #where_clause {
+            fn from_params(
+                params: &::fastcgi::record::Params,
+            ) -> ::core::result::Result<Self, ::fastcgi::record::FromParamsError> {
+                ::core::result::Result::Ok(Self {
+                    #(#fields,)*
+                })
+            }
+        }
+    })
+}
+
+/// Returns the name in the `#[param("NAME")]` attribute of a field.
+fn param_name(field: &syn::Field) -> Result<LitStr, Error> {
+    let mut attrs = field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("param"));
+
+    let Some(attr) = attrs.next() else {
+        return Err(Error::new(
+            field.span(),
+            "missing a #[param(\"NAME\")] attribute",
+        ));
+    };
+
+    if let Some(duplicate) = attrs.next() {
+        return Err(Error::new(duplicate.span(), "duplicate #[param] attribute"));
+    }
+
+    attr.parse_args()
+}
+
+/// Returns `T` if the type is written as `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+
+    let segment = path.path.segments.last()?;
+
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    match args.args.first()? {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
@@ -0,0 +1,156 @@
+//! Transports which capture and replay the bytes of a connection, for debugging.
+//!
+//! A connection which fails to decode the frames of a specific peer can be wrapped in a
+//! [`CaptureTransport`], after which the captured bytes can be attached to a bug report, or be
+//! replayed with a [`ReplayTransport`] to build a regression test.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, Bytes};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// The bytes which were read from and written to a transport.
+#[derive(Debug, Clone, Default)]
+pub struct Capture {
+    inner: Arc<Mutex<Captured>>,
+}
+
+#[derive(Debug, Default)]
+struct Captured {
+    read: Vec<u8>,
+    written: Vec<u8>,
+}
+
+impl Capture {
+    /// Returns the bytes which were read so far.
+    pub fn read(&self) -> Vec<u8> {
+        self.inner.lock().unwrap().read.clone()
+    }
+
+    /// Returns the bytes which were written so far.
+    pub fn written(&self) -> Vec<u8> {
+        self.inner.lock().unwrap().written.clone()
+    }
+}
+
+/// A transport which records all bytes which are read from and written to the inner transport.
+#[derive(Debug)]
+pub struct CaptureTransport<T> {
+    inner: T,
+    capture: Capture,
+}
+
+impl<T: AsyncRead + AsyncWrite> CaptureTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            capture: Capture::default(),
+        }
+    }
+
+    /// Returns a handle to the captured bytes, which remains valid after the transport is moved
+    /// into a client or server.
+    pub fn capture(&self) -> Capture {
+        self.capture.clone()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for CaptureTransport<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(())) = poll {
+            let mut captured = self.capture.inner.lock().unwrap();
+            captured.read.extend_from_slice(&buf.filled()[filled..]);
+        }
+
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for CaptureTransport<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+
+        if let Poll::Ready(Ok(n)) = poll {
+            let mut captured = self.capture.inner.lock().unwrap();
+            captured.written.extend_from_slice(&buf[..n]);
+        }
+
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// A transport which replays captured bytes as if they were read from a peer.
+///
+/// Written bytes are discarded, as the replayed peer doesn't react to them.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    read: Bytes,
+}
+
+impl ReplayTransport {
+    pub fn new(read: impl Into<Bytes>) -> Self {
+        Self { read: read.into() }
+    }
+}
+
+impl AsyncRead for ReplayTransport {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = buf.remaining().min(self.read.len());
+
+        buf.put_slice(&self.read[..n]);
+        self.read.advance(n);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for ReplayTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
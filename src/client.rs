@@ -1,51 +1,287 @@
-use tokio::io::{AsyncRead, AsyncWrite};
+use std::time::Duration;
+#[cfg(feature = "tls")]
+use std::{io, sync::Arc};
 
+use bytes::Bytes;
+#[cfg(feature = "unstable")]
+use bytes::BytesMut;
+use futures::{stream, Sink, Stream};
+#[cfg(feature = "tls")]
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time,
+};
+#[cfg(feature = "tls")]
+use tokio_rustls::{
+    rustls::{pki_types::ServerName, ClientConfig},
+    TlsConnector,
+};
+
+#[cfg(feature = "unstable")]
 use crate::{
+    codec::Frame,
+    meta::{self, Meta},
+    record::{EncodeChunk, RecordType},
+};
+
+use crate::{
+    codec::FrameStats,
     conn::{
-        connection::{Connection, ConnectionRecvError, ConnectionSendError},
+        config::Config,
+        connection::{Connection, ConnectionSendError},
+        defrag::{Defrag, Defragmenter},
         endpoint,
-        state::client::ParseResponseError,
+        state::client,
     },
-    request::Request,
-    response::Response,
+    record::{AbortRequest, Id, IntoRecord},
+    request::{Request, SentLengths},
+    response::{RequestMetrics, Response},
     FastcgiClientError,
 };
 
 /// TODO: design API.
-pub struct Client<T> {
-    connection: Connection<T, endpoint::Client>,
+pub struct Client<T, D: Defragmenter = Defrag> {
+    connection: Connection<T, endpoint::Client<D>>,
 }
 
 impl<T: AsyncRead + AsyncWrite> Client<T> {
     pub fn new(transport: T) -> Self {
+        Self::with_defragmenter(transport, Config::default())
+    }
+
+    pub fn with_config(transport: T, config: Config) -> Self {
+        Self::with_defragmenter(transport, config)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Client<tokio_rustls::client::TlsStream<TcpStream>> {
+    /// Connects to a server over TCP, and performs a TLS handshake with it.
+    ///
+    /// The certificate of the server is verified for `server_name`, using the root certificates
+    /// in `config`.
+    pub async fn connect_tls(
+        addr: impl ToSocketAddrs,
+        server_name: ServerName<'static>,
+        config: Arc<ClientConfig>,
+    ) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        let stream = TlsConnector::from(config)
+            .connect(server_name, stream)
+            .await?;
+
+        Ok(Self::new(stream))
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite, D: Defragmenter> Client<T, D> {
+    /// Creates a client which reassembles received streams with the defragmenter `D`.
+    pub fn with_defragmenter(transport: T, config: Config) -> Self {
         Self {
-            connection: Connection::new(transport),
+            connection: Connection::with_config(transport, config),
         }
     }
 }
 
-impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
+impl<T: AsyncRead + AsyncWrite + Unpin, D: Defragmenter> Client<T, D> {
+    /// Sends a request, and receives its response.
+    ///
+    /// The client can be reused for the next request once the response was received, as long as
+    /// the request was sent with `keep_conn` set.
+    ///
+    /// If the request has a deadline and the response doesn't arrive in time, the request is
+    /// aborted and `DeadlineExceeded` is returned.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is not cancellation safe. Requests aren't multiplexed, so dropping the future
+    /// may leave a partially written request or an unread response on the connection, and the
+    /// client should be discarded afterwards. Use a deadline to bound the time spent instead.
     pub async fn send(&mut self, req: Request) -> Result<Response, FastcgiClientError> {
-        self.send_request(req).await?;
+        self.send_with_stdin(req, stream::empty()).await
+    }
+
+    /// Sends a request followed by the chunks of `stdin` as they become available, and receives
+    /// its response.
+    ///
+    /// This allows a request body which is still being received, e.g. a streaming HTTP upload,
+    /// to be proxied through the receiver of a `futures::channel::mpsc` channel. The stdin of the
+    /// request itself is sent first, if set. Deadlines are handled as in [`Client::send`], and
+    /// this method isn't cancellation safe either.
+    pub async fn send_with_stdin<S>(
+        &mut self,
+        req: Request,
+        stdin: S,
+    ) -> Result<Response, FastcgiClientError>
+    where
+        S: Stream<Item = Bytes> + Unpin,
+    {
+        let Some(deadline) = req.get_deadline() else {
+            return self.send_inner(req, stdin).await;
+        };
+
+        let id = req.get_id();
+
+        match time::timeout(deadline, self.send_inner(req, stdin)).await {
+            Ok(result) => result,
+            Err(_) => {
+                self.connection.close_stream();
 
-        self.recv_response().await.map_err(FastcgiClientError::from)
+                // The server may no longer be reading, so failing to abort is ignored.
+                let _ = self.abort(id).await;
+
+                Err(FastcgiClientError::DeadlineExceeded)
+            }
+        }
     }
-}
 
-impl<T: AsyncWrite + Unpin> Client<T> {
-    async fn send_request(&mut self, req: Request) -> Result<(), ConnectionSendError> {
-        req.send(&mut self.connection).await?;
+    async fn send_inner<S>(
+        &mut self,
+        req: Request,
+        stdin: S,
+    ) -> Result<Response, FastcgiClientError>
+    where
+        S: Stream<Item = Bytes> + Unpin,
+    {
+        let start = self.connection.frame_stats();
+
+        let sent = req.send_with_stdin(&mut self.connection, stdin).await?;
+
+        let result = Response::recv(&mut self.connection)
+            .await
+            .map(|response| self.with_metrics(response.with_sent(sent), start));
+
+        self.connection.close_stream();
 
-        Ok(())
+        Ok(result?)
     }
-}
 
-impl<T: AsyncRead + Unpin> Client<T> {
-    async fn recv_response(&mut self) -> Result<Response, ConnectionRecvError<ParseResponseError>> {
-        let result = Response::recv(&mut self.connection).await;
+    /// Sends a request, and forwards stdout to the sink as it's received.
+    ///
+    /// Stdout is never reassembled, so the returned response doesn't contain it. This allows
+    /// responses larger than the available memory to be written directly into, e.g., the body
+    /// of an HTTP response. Stderr is still reassembled as usual.
+    ///
+    /// Like [`Client::send`], this method is not cancellation safe.
+    pub async fn send_streaming<S>(
+        &mut self,
+        req: Request,
+        sink: S,
+    ) -> Result<Response, FastcgiClientError>
+    where
+        S: Sink<Bytes> + Unpin,
+        S::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let start = self.connection.frame_stats();
+
+        let sent = self.send_request(req).await?;
+
+        self.connection.open_stream(
+            client::State::from_config(self.connection.config()).with_stdout_streaming(),
+        );
+
+        let result = Response::recv_streaming(&mut self.connection, sink)
+            .await
+            .map(|response| self.with_metrics(response.with_sent(sent), start));
 
         self.connection.close_stream();
 
-        result
+        Ok(result?)
+    }
+
+    /// Attaches the frames which were exchanged since `start` to the response, if enabled. The
+    /// stream must still be open, as the peak size of its defragmenters is read from it.
+    fn with_metrics(&self, response: Response, start: (FrameStats, FrameStats)) -> Response {
+        if !self.connection.config().request_metrics {
+            return response;
+        }
+
+        let (sent, received) = self.connection.frame_stats();
+
+        response.with_metrics(RequestMetrics {
+            sent: sent.since(start.0),
+            received: received.since(start.1),
+            peak_defrag_size: self.connection.peak_buffered(),
+        })
+    }
+}
+
+impl<T: AsyncWrite + Unpin, D: Defragmenter> Client<T, D> {
+    /// Sends an arbitrary frame, bypassing the typed record layer.
+    ///
+    /// The payload is not validated, and no padding is added. This is intended for fuzzing and
+    /// conformance testing of servers, e.g. sending malformed or unexpected frames.
+    #[cfg(feature = "unstable")]
+    pub async fn send_raw(
+        &mut self,
+        id: Id,
+        record_type: RecordType,
+        payload: BytesMut,
+    ) -> Result<(), ConnectionSendError> {
+        self.connection
+            .feed_raw(Frame::new(id, record_type, payload))
+            .await?;
+
+        self.connection.flush().await
+    }
+
+    /// Sends a complete stream record, e.g. the stdin of a request which was begun with
+    /// [`Client::send_raw`].
+    ///
+    /// ```no_run
+    /// # use bytes::Bytes;
+    /// # use fastcgi::{client::Client, record::{ByteSlice, Stdin}};
+    /// # async fn send(client: &mut Client<tokio::io::DuplexStream>) {
+    /// let stdin = Stdin(ByteSlice::new(Bytes::from("stdin")).unwrap());
+    ///
+    /// client.send_stream(1, stdin).await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// Only records which a client is allowed to send are accepted, so sending `Stderr` doesn't
+    /// compile:
+    ///
+    /// ```compile_fail
+    /// # use bytes::Bytes;
+    /// # use fastcgi::{client::Client, record::{ByteSlice, Stderr}};
+    /// # async fn send(client: &mut Client<tokio::io::DuplexStream>) {
+    /// let stderr = Stderr(ByteSlice::new(Bytes::from("stderr")).unwrap());
+    ///
+    /// client.send_stream(1, stderr).await.unwrap();
+    /// # }
+    /// ```
+    #[cfg(feature = "unstable")]
+    pub async fn send_stream<S>(&mut self, id: Id, record: S) -> Result<(), ConnectionSendError>
+    where
+        S: EncodeChunk + Meta<SentBy = meta::Client>,
+    {
+        self.connection.feed_stream(record.into_record(id)).await?;
+
+        self.connection.flush().await
+    }
+
+    async fn abort(&mut self, id: Id) -> Result<(), ConnectionSendError> {
+        self.connection
+            .feed_frame(AbortRequest.into_record(id))
+            .await?;
+
+        self.connection.flush().await
+    }
+
+    async fn send_request(&mut self, req: Request) -> Result<SentLengths, ConnectionSendError> {
+        req.send(&mut self.connection).await
+    }
+}
+
+impl<T: AsyncRead + Unpin, D: Defragmenter> Client<T, D> {
+    /// Reads the records which a server sends after a response on a `keep_conn` connection,
+    /// e.g. vendor records with trailing metadata, and passes them to the
+    /// [`Config::management_record_handler`].
+    ///
+    /// Waits up to `wait` for each record, and returns the number of records which were
+    /// received. Must be called between requests.
+    pub async fn recv_trailers(&mut self, wait: Duration) -> Result<usize, FastcgiClientError> {
+        Ok(self.connection.recv_trailers(wait).await?)
     }
 }
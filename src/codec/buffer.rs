@@ -1,22 +1,35 @@
 use bytes::{buf::UninitSlice, BufMut};
 
+use crate::record::DEFAULT_MAX_PAYLOAD_SIZE;
+
 use super::RingBuffer;
 
 /// A Wrapper struct around a RingBuffer.
 ///
-/// This struct provides write-only access to the underlying RingBuffer.
+/// This struct provides write-only access to the underlying RingBuffer. Writes are limited to
+/// the maximum payload size of a single frame.
 pub struct Buffer<'buf> {
     inner: &'buf mut RingBuffer,
+    limit: usize,
 }
 
 impl RingBuffer {
     /// Adds a write_only method to the underlying RingBuffer.
-    pub fn write_only(&mut self) -> Buffer {
-        Buffer { inner: self }
+    pub fn write_only(&mut self) -> Buffer<'_> {
+        Buffer {
+            inner: self,
+            limit: DEFAULT_MAX_PAYLOAD_SIZE,
+        }
     }
 }
 
 impl<'buf> Buffer<'buf> {
+    /// Lowers the maximum payload size of the frame which is written.
+    pub fn limit(mut self, n: usize) -> Self {
+        self.limit = self.limit.min(n);
+        self
+    }
+
     /// Returns the number of bytes that the buf can hold.
     pub fn capacity(&self) -> usize {
         self.inner.capacity()
@@ -44,7 +57,9 @@ impl<'buf> Buffer<'buf> {
 
     /// Returns the remaining write capacity until which the buf becomes full.
     pub fn remaining_write(&self) -> usize {
-        self.inner.remaining_write()
+        self.inner
+            .remaining_write()
+            .min(self.limit.saturating_sub(self.inner.remaining_read()))
     }
 }
 
@@ -53,14 +68,19 @@ where
     RingBuffer: BufMut,
 {
     fn remaining_mut(&self) -> usize {
-        self.inner.remaining_write()
+        self.remaining_write()
     }
 
     unsafe fn advance_mut(&mut self, cnt: usize) {
+        assert!(cnt <= self.remaining_write(), "buffer overflow");
         self.inner.advance_mut(cnt)
     }
 
     fn chunk_mut(&mut self) -> &mut UninitSlice {
-        self.inner.chunk_mut()
+        let n = self.remaining_write();
+        let chunk = self.inner.chunk_mut();
+        let len = chunk.len().min(n);
+
+        &mut chunk[..len]
     }
 }
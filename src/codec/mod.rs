@@ -1,10 +1,14 @@
 mod buffer;
 mod ring_buffer;
+#[cfg(feature = "unstable")]
+mod transport;
 
 pub use buffer::*;
 pub(crate) use ring_buffer::*;
+#[cfg(feature = "unstable")]
+pub use transport::*;
 
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use tokio_util::codec::{Decoder, Encoder};
 
 use crate::meta::{self, Meta};
@@ -13,16 +17,17 @@ use crate::record::{
     RecordType, StreamChunker, DEFAULT_MAX_PAYLOAD_SIZE, HEADER_SIZE,
 };
 
-/// Unparsed frame.
+/// Unparsed frame, i.e. the request id, the record type and the payload of a record, without its
+/// padding.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct Frame {
+pub struct Frame {
     pub(crate) id: Id,
     pub(crate) record_type: RecordType,
     pub(crate) payload: BytesMut,
 }
 
 impl Frame {
-    pub(crate) fn new(id: Id, record_type: RecordType, payload: BytesMut) -> Self {
+    pub fn new(id: Id, record_type: RecordType, payload: BytesMut) -> Self {
         Self {
             id,
             record_type,
@@ -39,6 +44,48 @@ impl Frame {
     }
 }
 
+/// Decodes a single frame from the start of `src`, stripping its padding.
+///
+/// Returns `Ok(None)` if `src` doesn't contain a complete frame. This is a thin wrapper around the
+/// decoder of the codec which doesn't panic on any input, making it suitable as a fuzz target.
+pub fn decode_frame(src: &[u8]) -> Result<Option<(Id, RecordType, Bytes)>, DecodeCodecError> {
+    let mut src = BytesMut::from(src);
+
+    let frame = FastCgiCodec::new().decode(&mut src)?;
+
+    Ok(frame.map(|frame| {
+        let (id, record_type, payload) = frame.into_parts();
+
+        (id, record_type, payload.freeze())
+    }))
+}
+
+/// Counters of the frames which were encoded or decoded by a codec.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    pub frames: u64,
+    /// The number of bytes on the wire, including headers and padding.
+    pub bytes: u64,
+    pub padding: u64,
+}
+
+impl FrameStats {
+    /// Returns the counts since an earlier snapshot of the same counters.
+    pub fn since(self, earlier: FrameStats) -> FrameStats {
+        FrameStats {
+            frames: self.frames - earlier.frames,
+            bytes: self.bytes - earlier.bytes,
+            padding: self.padding - earlier.padding,
+        }
+    }
+
+    fn add(&mut self, frames: u64, bytes: usize, padding: usize) {
+        self.frames += frames;
+        self.bytes += bytes as u64;
+        self.padding += padding as u64;
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum DecodeState {
     Header,
@@ -46,23 +93,106 @@ enum DecodeState {
     Padding(u8),
 }
 
+/// The codec which encodes records into frames, and decodes frames from a byte stream.
 #[derive(Debug)]
-pub(crate) struct FastCgiCodec {
+pub struct FastCgiCodec {
     // Encode
     buffer: RingBuffer,
+    max_frame_size: u16,
+    count_frames: bool,
+    encoded: FrameStats,
 
     // Decode
     state: DecodeState,
+    ignore_reserved: bool,
+    max_reservation: Option<usize>,
+    max_buffered: Option<usize>,
+    decoded: FrameStats,
 }
 
 impl FastCgiCodec {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             buffer: RingBuffer::with_capacity(DEFAULT_MAX_PAYLOAD_SIZE + 1),
+            max_frame_size: u16::MAX,
+            count_frames: false,
+            encoded: FrameStats::default(),
             state: DecodeState::Header,
+            ignore_reserved: false,
+            max_reservation: None,
+            max_buffered: None,
+            decoded: FrameStats::default(),
         }
     }
 
+    /// Accepts headers with a nonzero reserved byte, instead of rejecting them as corrupted.
+    ///
+    /// The spec requires the byte to be 0, but some implementations don't clear it.
+    pub fn ignore_reserved(mut self) -> Self {
+        self.ignore_reserved = true;
+        self
+    }
+
+    /// Caps the space which is reserved up front for the body of a frame once its header is
+    /// decoded. Beyond this, the buffer only grows as the bytes of the body arrive.
+    ///
+    /// By default, the full declared body is reserved. Capping it prevents peers from making
+    /// the codec allocate up to 64KB per connection with headers whose body never arrives. A
+    /// peer which does send the body still fills the buffer, see [`FastCgiCodec::max_buffered`].
+    pub fn max_reservation(mut self, n: usize) -> Self {
+        self.max_reservation = Some(n);
+        self
+    }
+
+    /// Bounds the bytes which are held for a frame in progress: the bytes which are buffered,
+    /// plus the space which is reserved for the rest of the frame.
+    ///
+    /// A frame which would exceed it fails with `ExceededMaximumBufferSize` as soon as its header
+    /// is decoded, instead of being buffered. Unbounded by default, in which case a single frame
+    /// holds at most 64KB of content and 255 bytes of padding.
+    pub fn max_buffered(mut self, n: usize) -> Self {
+        self.max_buffered = Some(n);
+        self
+    }
+
+    /// Sets the maximum content length of the frames of encoded streams, e.g. stdin, which are
+    /// otherwise split at 64KB.
+    ///
+    /// Smaller frames let the peer see streamed data in smaller increments, or fit the buffers
+    /// of peers which don't accept full frames. A name-value pair can't be split across frames,
+    /// so params with a pair which exceeds the size fail to encode. Sizes below 1 are raised to 1.
+    pub fn max_frame_size(mut self, n: u16) -> Self {
+        self.max_frame_size = n.max(1);
+        self
+    }
+
+    /// Counts the frames which are encoded and decoded, see [`FastCgiCodec::encoded_stats`].
+    /// Disabled by default.
+    pub fn count_frames(mut self) -> Self {
+        self.count_frames = true;
+        self
+    }
+
+    /// Returns the counters of all frames which were encoded so far, if counting is enabled.
+    pub fn encoded_stats(&self) -> FrameStats {
+        self.encoded
+    }
+
+    /// Returns the counters of all frames which were decoded so far, if counting is enabled.
+    pub fn decoded_stats(&self) -> FrameStats {
+        self.decoded
+    }
+
+    /// Discards the buffered input, and expects a header next.
+    ///
+    /// After a decode error the codec may be left in the middle of a frame, and the corrupted
+    /// bytes are still buffered. Resynchronizing allows a connection to attempt to recover
+    /// from a corrupted frame, as long as the peer continues at a frame boundary.
+    pub fn resync(&mut self, src: &mut BytesMut) {
+        src.clear();
+        self.state = DecodeState::Header;
+    }
+
     /// Encodes the header, the currently encoded record body, and the padding of a record.
     fn encode_record(&mut self, header: Header, dst: &mut BytesMut) {
         let content_length = self.buffer.remaining() as u16;
@@ -82,13 +212,36 @@ impl FastCgiCodec {
 
         dst.put(&mut self.buffer);
         dst.put_bytes(0, padding_length as usize);
+
+        if self.count_frames {
+            let padding_length = padding_length as usize;
+
+            self.encoded.add(
+                1,
+                HEADER_SIZE + content_length as usize + padding_length,
+                padding_length,
+            );
+        }
     }
 
     /// Decodes a header and reserves space to fit the entire record body, including padding bytes.
-    fn decode_header(src: &mut BytesMut) -> Result<Option<(Header, u16)>, DecodeCodecError> {
-        if let Some((header, content_length, padding_length)) = Header::decode(src)? {
+    fn decode_header(&self, src: &mut BytesMut) -> Result<Option<(Header, u16)>, DecodeCodecError> {
+        if let Some((header, content_length, padding_length)) =
+            Header::decode(src, self.ignore_reserved)?
+        {
             // Grow the buffer for the expected data, plus padding.
-            src.reserve(content_length as usize + padding_length as usize);
+            let n = content_length as usize + padding_length as usize;
+            let reservation = self.max_reservation.map_or(n, |max| n.min(max));
+
+            // Either the bytes of the frame are already buffered, or space is reserved for them.
+            // The buffer may also hold the frames which follow, which aren't counted.
+            let outstanding = src.len().min(n).max(reservation);
+
+            if self.max_buffered.is_some_and(|max| outstanding > max) {
+                return Err(DecodeCodecError::ExceededMaximumBufferSize);
+            }
+
+            src.reserve(reservation);
 
             Ok(Some((header, content_length)))
         } else {
@@ -105,15 +258,20 @@ impl FastCgiCodec {
         Some(src.split_to(content_length as usize))
     }
 
-    /// Consumes n padding bytes from the source.
-    fn consume_padding(n: u8, src: &mut BytesMut) -> Option<()> {
-        if src.len() < n as usize {
-            return None;
-        }
+    /// Consumes up to n padding bytes from the source, returning the number of bytes which are
+    /// still to be received.
+    fn consume_padding(n: u8, src: &mut BytesMut) -> u8 {
+        let consumed = src.len().min(n as usize);
+
+        src.advance(consumed);
 
-        src.advance(n as usize);
+        n - consumed as u8
+    }
+}
 
-        Some(())
+impl Default for FastCgiCodec {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -156,7 +314,7 @@ where
     ) -> Result<(), Self::Error> {
         let option = record
             .body
-            .encode(&mut self.buffer.write_only())
+            .encode(&mut self.buffer.write_only().limit(self.max_frame_size as usize))
             .transpose()
             .map_err(|err| {
                 // Advance the read cursor past the invalid data.
@@ -191,6 +349,30 @@ where
     }
 }
 
+// Raw frames bypass the typed record layer, and are encoded without padding.
+#[cfg(feature = "unstable")]
+impl Encoder<Frame> for FastCgiCodec {
+    type Error = EncodeCodecError;
+
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let content_length =
+            u16::try_from(frame.payload.len()).map_err(|_| EncodeCodecError::MaxLengthExceeded)?;
+
+        dst.reserve(HEADER_SIZE + content_length as usize);
+
+        Header::encode(frame.record_type, frame.id, content_length, 0, dst);
+
+        dst.put(frame.payload);
+
+        if self.count_frames {
+            self.encoded
+                .add(1, HEADER_SIZE + content_length as usize, 0);
+        }
+
+        Ok(())
+    }
+}
+
 // Flush
 impl Encoder<()> for FastCgiCodec {
     type Error = EncodeCodecError;
@@ -205,44 +387,73 @@ impl Decoder for FastCgiCodec {
     type Error = DecodeCodecError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        // Eat the padding at the end of the previous request.
-        // This is done at the start instead of end to return the previous Frame ASAP.
-        if let DecodeState::Padding(skip) = self.state {
-            match Self::consume_padding(skip, src) {
-                Some(_) => self.state = DecodeState::Header,
-                None => return Ok(None),
-            }
-        }
-
-        // Decode the header, if the header was already decoded, return the
-        // decoded value.
-        let (header, content_length) = match self.state {
-            DecodeState::Header => match Self::decode_header(src)? {
-                Some(x) => {
-                    self.state = DecodeState::Payload(x);
-                    x
+        loop {
+            match self.state {
+                // Eat the padding at the end of the previous request.
+                // This is done at the start instead of end to return the previous Frame ASAP.
+                // Padding may be split across reads, so the remaining count is kept.
+                DecodeState::Padding(skip) => {
+                    let remaining = Self::consume_padding(skip, src);
+
+                    if self.count_frames {
+                        let consumed = (skip - remaining) as usize;
+                        self.decoded.add(0, consumed, consumed);
+                    }
+
+                    if remaining == 0 {
+                        self.state = DecodeState::Header;
+                    } else {
+                        self.state = DecodeState::Padding(remaining);
+                        return Ok(None);
+                    }
                 }
-                None => return Ok(None),
-            },
-            DecodeState::Payload(x) => x,
-            _ => unreachable!(),
-        };
 
-        // Decode body and reserve space for the next header.
-        match Self::extract_body(content_length, src) {
-            Some(data) => {
-                if let Some(Padding::Static(n)) = header.padding {
-                    self.state = DecodeState::Padding(n);
-                } else {
-                    self.state = DecodeState::Header;
+                DecodeState::Header => match self.decode_header(src)? {
+                    Some(x) => {
+                        if self.count_frames {
+                            self.decoded.add(1, HEADER_SIZE, 0);
+                        }
+
+                        self.state = DecodeState::Payload(x);
+                    }
+                    None => return Ok(None),
+                },
+
+                // Decode body and reserve space for the next header.
+                DecodeState::Payload((header, content_length)) => {
+                    let Some(data) = Self::extract_body(content_length, src) else {
+                        return Ok(None);
+                    };
+
+                    if self.count_frames {
+                        self.decoded.add(0, content_length as usize, 0);
+                    }
+
+                    if let Some(Padding::Static(n)) = header.padding {
+                        self.state = DecodeState::Padding(n);
+                    } else {
+                        self.state = DecodeState::Header;
+                    }
+
+                    src.reserve(HEADER_SIZE);
+
+                    // Padding is stripped during the decoding of frames.
+                    return Ok(Some(Frame::new(header.id, header.record_type, data)));
                 }
+            }
+        }
+    }
 
-                src.reserve(HEADER_SIZE);
+    /// Fails if the transport ended in the middle of a frame, so that a truncated stream isn't
+    /// mistaken for a clean close.
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(frame) = self.decode(src)? {
+            return Ok(Some(frame));
+        }
 
-                // Padding is stripped during the decoding of frames.
-                Ok(Some(Frame::new(header.id, header.record_type, data)))
-            }
-            None => Ok(None),
+        match self.state {
+            DecodeState::Header if src.is_empty() => Ok(None),
+            _ => Err(DecodeCodecError::UnexpectedEof),
         }
     }
 }
@@ -270,6 +481,9 @@ impl From<std::io::Error> for EncodeCodecError {
 pub enum DecodeCodecError {
     IncompatibleVersion,
     CorruptedHeader,
+    UnexpectedEof,
+    /// A frame would exceed [`FastCgiCodec::max_buffered`].
+    ExceededMaximumBufferSize,
     StdIoError(std::io::Error),
 }
 
@@ -278,3 +492,270 @@ impl From<std::io::Error> for DecodeCodecError {
         DecodeCodecError::StdIoError(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::record::{EndRequest, IntoRecord, ProtocolStatus, Standard};
+
+    #[test]
+    fn encode_with_maximum_padding() {
+        let mut record = EndRequest::new(0, ProtocolStatus::RequestComplete).into_record(1);
+        record.header.padding = Some(Padding::Static(u8::MAX));
+
+        let mut codec = FastCgiCodec::new();
+        let mut dst = BytesMut::new();
+        codec.encode(record, &mut dst).unwrap();
+
+        assert_eq!(dst.len(), HEADER_SIZE + 8 + u8::MAX as usize);
+
+        let frame = codec.decode(&mut dst).unwrap().unwrap();
+        assert_eq!(frame.record_type, Standard::EndRequest);
+        assert_eq!(frame.payload.len(), 8);
+
+        // The padding is consumed before the next frame.
+        assert!(codec.decode(&mut dst).unwrap().is_none());
+        assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn frame_stats() {
+        let mut codec = FastCgiCodec::new().count_frames();
+        let mut dst = BytesMut::new();
+
+        let record = EndRequest::new(0, ProtocolStatus::RequestComplete).into_record(1);
+        codec.encode(record, &mut dst).unwrap();
+
+        let expected = FrameStats {
+            frames: 1,
+            bytes: 16,
+            padding: 0,
+        };
+        assert_eq!(codec.encoded_stats(), expected);
+
+        let mut src = BytesMut::from(&[1, 6, 0, 1, 0, 1, 7, 0, b'a', 0, 0, 0, 0, 0, 0, 0][..]);
+        codec.decode(&mut src).unwrap().unwrap();
+        assert!(codec.decode(&mut src).unwrap().is_none());
+
+        let expected = FrameStats {
+            frames: 1,
+            bytes: 16,
+            padding: 7,
+        };
+        assert_eq!(codec.decoded_stats(), expected);
+
+        let mut codec = FastCgiCodec::new();
+        let record = EndRequest::new(0, ProtocolStatus::RequestComplete).into_record(1);
+        codec.encode(record, &mut dst).unwrap();
+
+        assert_eq!(codec.encoded_stats(), FrameStats::default());
+    }
+
+    #[test]
+    fn decode_single_frame() {
+        let src = [1, 6, 0, 1, 0, 3, 5, 0, b'a', b'b', b'c', 0, 0, 0, 0, 0];
+
+        let (id, record_type, payload) = decode_frame(&src).unwrap().unwrap();
+
+        assert_eq!(id, 1);
+        assert_eq!(record_type, Standard::Stdout);
+        assert_eq!(&payload[..], b"abc");
+    }
+
+    #[test]
+    fn decode_consecutive_padded_frames() {
+        let mut codec = FastCgiCodec::new();
+        let mut src = BytesMut::from(
+            &[
+                1, 6, 0, 1, 0, 1, 7, 0, b'a', 0, 0, 0, 0, 0, 0, 0, // Stdout
+                1, 6, 0, 1, 0, 0, 0, 0, // EndOfStream
+            ][..],
+        );
+
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(&frame.payload[..], b"a");
+
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert!(frame.payload.is_empty());
+
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn decode_incomplete_frame() {
+        let src = [1, 6, 0, 1, 0, 3, 0, 0, b'a'];
+
+        assert!(decode_frame(&src[..4]).unwrap().is_none());
+        assert!(decode_frame(&src).unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_invalid_record_type() {
+        let src = [1, 0, 0, 1, 0, 0, 0, 0];
+
+        let (_, record_type, _) = decode_frame(&src).unwrap().unwrap();
+
+        assert_eq!(u8::from(record_type), 0);
+    }
+
+    #[test]
+    fn decode_header_split_across_reads() {
+        let mut codec = FastCgiCodec::new();
+        let mut src = BytesMut::new();
+        let mut frames = Vec::new();
+
+        let input = [
+            1, 6, 0, 1, 0, 3, 0, 0, b'a', b'b', b'c', // Stdout
+            1, 6, 0, 1, 0, 0, 0, 0, // EndOfStream
+        ];
+
+        // Simulates a socket which delivers a single byte per read.
+        for byte in input {
+            src.put_u8(byte);
+
+            if let Some(frame) = codec.decode(&mut src).unwrap() {
+                frames.push(frame);
+            }
+        }
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(&frames[0].payload[..], b"abc");
+        assert!(frames[1].payload.is_empty());
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn decode_padding_split_across_reads() {
+        let mut codec = FastCgiCodec::new();
+        let mut src = BytesMut::from(&[1, 6, 0, 1, 0, 1, 7, 0, b'a', 0, 0][..]);
+
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(&frame.payload[..], b"a");
+
+        // Only part of the padding has been received.
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert!(src.is_empty());
+
+        // The rest of the padding arrives together with the next frame.
+        src.put_slice(&[0, 0, 0, 0, 0, 1, 6, 0, 1, 0, 1, 0, 0, b'b']);
+
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(&frame.payload[..], b"b");
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn decode_padded_empty_frame() {
+        let mut codec = FastCgiCodec::new();
+        let mut src = BytesMut::from(
+            &[
+                1, 6, 0, 1, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, // Padded EndOfStream
+                1, 3, 0, 1, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // EndRequest
+            ][..],
+        );
+
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(frame.record_type, Standard::Stdout);
+        assert!(frame.payload.is_empty());
+
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(frame.record_type, Standard::EndRequest);
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn decode_truncated_frame_at_eof() {
+        let mut codec = FastCgiCodec::new();
+        let mut src = BytesMut::from(&[1, 6, 0, 1, 0, 3, 0, 0, b'a'][..]);
+
+        assert!(matches!(
+            codec.decode_eof(&mut src),
+            Err(DecodeCodecError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn decode_clean_eof() {
+        let mut codec = FastCgiCodec::new();
+        let mut src = BytesMut::from(&[1, 6, 0, 1, 0, 1, 7, 0, b'a', 0, 0, 0, 0, 0, 0, 0][..]);
+
+        assert!(codec.decode_eof(&mut src).unwrap().is_some());
+        assert!(codec.decode_eof(&mut src).unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_with_capped_reservation() {
+        // A header which declares the maximum body size, without the body.
+        let src = [1, 6, 0, 1, 0xff, 0xff, 0, 0];
+
+        let mut codec = FastCgiCodec::new();
+        let mut buffer = BytesMut::from(&src[..]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        assert!(buffer.capacity() >= u16::MAX as usize);
+
+        let mut codec = FastCgiCodec::new().max_reservation(1024);
+        let mut buffer = BytesMut::from(&src[..]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        assert!(buffer.capacity() < u16::MAX as usize);
+    }
+
+    #[test]
+    fn decode_with_bounded_buffer() {
+        let mut codec = FastCgiCodec::new().max_buffered(1024);
+
+        // Many small frames which are buffered at once.
+        let mut src = BytesMut::new();
+        for _ in 0..200 {
+            src.extend_from_slice(&[1, 6, 0, 1, 0, 2, 0, 0, b'o', b'k']);
+        }
+
+        while let Some(frame) = codec.decode(&mut src).unwrap() {
+            assert_eq!(&frame.payload[..], b"ok");
+        }
+
+        // A header which declares the maximum body size, without the body.
+        let mut src = BytesMut::from(&[1, 6, 0, 1, 0xff, 0xff, 0, 0][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(DecodeCodecError::ExceededMaximumBufferSize)
+        ));
+        assert!(src.capacity() < u16::MAX as usize);
+    }
+
+    #[test]
+    fn decode_after_resync() {
+        let mut codec = FastCgiCodec::new();
+
+        // A header with an unsupported version.
+        let mut src = BytesMut::from(&[2, 6, 0, 1, 0, 3, 0, 0, b'b', b'a', b'd'][..]);
+        assert!(codec.decode(&mut src).is_err());
+        assert!(codec.decode(&mut src).is_err());
+
+        codec.resync(&mut src);
+        assert!(src.is_empty());
+
+        src.extend_from_slice(&[1, 6, 0, 1, 0, 2, 0, 0, b'o', b'k']);
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(&frame.payload[..], b"ok");
+    }
+
+    #[test]
+    fn decode_nonzero_reserved_byte() {
+        let src = [1, 6, 0, 1, 0, 1, 0, 0xff, b'a'];
+
+        let mut codec = FastCgiCodec::new();
+        assert!(matches!(
+            codec.decode(&mut BytesMut::from(&src[..])),
+            Err(DecodeCodecError::CorruptedHeader)
+        ));
+
+        let mut codec = FastCgiCodec::new().ignore_reserved();
+        let frame = codec
+            .decode(&mut BytesMut::from(&src[..]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(&frame.payload[..], b"a");
+    }
+}
@@ -61,15 +61,6 @@ impl<T: AsRef<[u8]>> RingBuffer<T> {
         self.rd
     }
 
-    /// Set the read cursor position
-    pub fn set_position(&mut self, position: u64) {
-        assert!(
-            position <= self.wr && position + self.capacity() as u64 >= self.wr,
-            "position out of bounds"
-        );
-        self.rd = position;
-    }
-
     /// Return the number of buffered bytes
     pub fn len(&self) -> usize {
         if self.wr >= self.capacity() as u64 {
@@ -84,12 +75,6 @@ impl<T: AsRef<[u8]>> RingBuffer<T> {
         self.len() == 0
     }
 
-    /// Resets all internal state to the initial state.
-    pub fn clear(&mut self) {
-        self.rd = 0;
-        self.wr = 0;
-    }
-
     /// Returns the number of bytes remaining to read.
     pub fn remaining_read(&self) -> usize {
         (self.wr - self.rd) as usize
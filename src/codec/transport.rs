@@ -0,0 +1,68 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{Sink, Stream};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::Framed;
+
+use super::{DecodeCodecError, EncodeCodecError, FastCgiCodec, Frame};
+
+/// A transport which sends and receives raw frames.
+///
+/// This bypasses the parsers of the client and server, which allows building custom
+/// multiplexing or proxying logic directly on frames. Received frames are neither validated nor
+/// reassembled, and the padding of received frames is stripped.
+///
+/// Frames are sent without padding, and aren't checked against the records which the endpoint is
+/// allowed to send. Typed requests can be converted with [`crate::request::Request::into_frames`].
+#[derive(Debug)]
+pub struct FrameTransport<T> {
+    inner: Framed<T, FastCgiCodec>,
+}
+
+impl<T: AsyncRead + AsyncWrite> FrameTransport<T> {
+    pub fn new(transport: T) -> Self {
+        Self::with_codec(transport, FastCgiCodec::new())
+    }
+
+    /// Creates a transport with a configured codec, e.g. one which ignores the reserved byte.
+    pub fn with_codec(transport: T, codec: FastCgiCodec) -> Self {
+        Self {
+            inner: Framed::new(transport, codec),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: AsyncRead + Unpin> Stream for FrameTransport<T> {
+    type Item = Result<Frame, DecodeCodecError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> Sink<Frame> for FrameTransport<T> {
+    type Error = EncodeCodecError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Frame>::poll_ready(Pin::new(&mut self.inner), cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Frame) -> Result<(), Self::Error> {
+        Sink::<Frame>::start_send(Pin::new(&mut self.inner), item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Frame>::poll_flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Frame>::poll_close(Pin::new(&mut self.inner), cx)
+    }
+}
@@ -0,0 +1,284 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use crate::codec::Frame;
+
+/// The default initial capacity of the read buffer of the transport, which matches the default
+/// of `tokio_util::codec::Framed`.
+pub const DEFAULT_TRANSPORT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// How a connection handles records of a type which the parser doesn't expect.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnexpectedRecordPolicy {
+    /// Fail the request with an `UnexpectedRecordType` error.
+    #[default]
+    Strict,
+    /// Ignore the record, and continue parsing the stream.
+    Skip,
+}
+
+/// A handler for records which aren't part of a request, e.g. management records. It may
+/// capture state, e.g. a channel to forward the records to.
+pub type RecordHandler = Arc<dyn Fn(&Frame) + Send + Sync>;
+
+/// Wraps a handler, as closures don't implement `Debug`.
+#[derive(Clone)]
+pub(crate) struct Handler(RecordHandler);
+
+impl Handler {
+    pub(crate) fn call(&self, frame: &Frame) {
+        (self.0)(frame)
+    }
+}
+
+impl fmt::Debug for Handler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Handler")
+    }
+}
+
+/// Configuration of a client or server connection.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub(crate) transport_buffer_size: usize,
+    pub(crate) max_response_size: Option<usize>,
+    pub(crate) max_stdout_size: Option<usize>,
+    pub(crate) max_stderr_size: Option<usize>,
+    pub(crate) max_stream_size: Option<usize>,
+    pub(crate) unexpected_record_policy: UnexpectedRecordPolicy,
+    pub(crate) require_end_of_stderr: bool,
+    pub(crate) allow_empty_params: bool,
+    pub(crate) max_content_length: Option<u64>,
+    pub(crate) ignore_reserved: bool,
+    pub(crate) max_decode_reservation: Option<usize>,
+    pub(crate) max_decode_buffer_size: Option<usize>,
+    pub(crate) flush_stream_records: bool,
+    pub(crate) request_metrics: bool,
+    pub(crate) max_frame_size: Option<u16>,
+    pub(crate) management_record_handler: Option<Handler>,
+    #[cfg(feature = "extensions")]
+    pub(crate) custom_record_handler: Option<Handler>,
+    pub(crate) first_frame_timeout: Option<Duration>,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) write_timeout: Option<Duration>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the initial capacity of the read buffer of the transport.
+    ///
+    /// Larger buffers reduce the number of read calls for large responses, as a full frame
+    /// can hold up to 64KB of data.
+    pub fn transport_buffer_size(mut self, n: usize) -> Self {
+        self.transport_buffer_size = n;
+        self
+    }
+
+    /// Sets the maximum combined size of the stdout and stderr of a response.
+    ///
+    /// A response exceeding it isn't sent, and the request is ended with the `Overloaded`
+    /// protocol status instead. This protects the server against runaway handlers. Only applies
+    /// to servers, and is unlimited by default.
+    pub fn max_response_size(mut self, n: usize) -> Self {
+        self.max_response_size = Some(n);
+        self
+    }
+
+    /// Sets the maximum size of the stdout of a received response.
+    ///
+    /// A larger stdout fails the response with `ExceededMaximumStdoutSize`, also when it's
+    /// streamed. Only applies to clients, and is only limited by the defragmenter by default.
+    pub fn max_stdout_size(mut self, n: usize) -> Self {
+        self.max_stdout_size = Some(n);
+        self
+    }
+
+    /// Sets the maximum size of the stderr of a received response.
+    ///
+    /// Stderr is usually small, so a tight limit allows treating backends which flood it as
+    /// misbehaving. A larger stderr fails the response with `ExceededMaximumStderrSize`. Only
+    /// applies to clients.
+    pub fn max_stderr_size(mut self, n: usize) -> Self {
+        self.max_stderr_size = Some(n);
+        self
+    }
+
+    /// Sets the maximum size of a single stream which the defragmenter reassembles, e.g. the
+    /// params or stdin of a request, or the stdout of a response.
+    ///
+    /// Applies to clients and servers. Defaults to the maximum of the defragmenter, which is
+    /// 64MB for [`crate::conn::Defrag`].
+    pub fn max_stream_size(mut self, n: usize) -> Self {
+        self.max_stream_size = Some(n);
+        self
+    }
+
+    /// Sets how records of an unexpected type are handled.
+    ///
+    /// Skipping them allows interoperating with peers which send vendor-specific records.
+    /// Defaults to [`UnexpectedRecordPolicy::Strict`].
+    pub fn unexpected_record_policy(mut self, policy: UnexpectedRecordPolicy) -> Self {
+        self.unexpected_record_policy = policy;
+        self
+    }
+
+    /// Requires servers to explicitly end the stderr stream before ending the request.
+    ///
+    /// By default, a response without a stderr stream is accepted, as some servers never open
+    /// it. Either way, a stderr stream which is empty results in a response without stderr.
+    /// Only applies to clients.
+    pub fn require_end_of_stderr(mut self, require: bool) -> Self {
+        self.require_end_of_stderr = require;
+        self
+    }
+
+    /// Accepts requests without any params.
+    ///
+    /// The spec doesn't require params, but every role relies on them in practice, so an empty
+    /// params stream is rejected with `ParamsMustBeLargerThanZero` by default. Allowing it suits,
+    /// e.g., authorizers which decide based on minimal or no params. Only applies to servers.
+    pub fn allow_empty_params(mut self, allow: bool) -> Self {
+        self.allow_empty_params = allow;
+        self
+    }
+
+    /// Sets the maximum body size which a request may declare in its `CONTENT_LENGTH` param.
+    ///
+    /// A request declaring a larger body fails with `ExceededMaximumContentLength` as soon as its
+    /// params are received, before any of its stdin is buffered. Requests without a valid
+    /// `CONTENT_LENGTH` are only limited by the defragmenter. Only applies to servers, and is
+    /// unlimited by default.
+    pub fn max_content_length(mut self, n: u64) -> Self {
+        self.max_content_length = Some(n);
+        self
+    }
+
+    /// Accepts frames with a nonzero reserved header byte, instead of rejecting them as
+    /// corrupted. See [`crate::codec::FastCgiCodec::ignore_reserved`].
+    pub fn ignore_reserved(mut self, ignore: bool) -> Self {
+        self.ignore_reserved = ignore;
+        self
+    }
+
+    /// Caps the space which is reserved up front for the body of a received frame. See
+    /// [`crate::codec::FastCgiCodec::max_reservation`].
+    pub fn max_decode_reservation(mut self, n: usize) -> Self {
+        self.max_decode_reservation = Some(n);
+        self
+    }
+
+    /// Bounds the bytes which are buffered and reserved for a received frame in progress. See
+    /// [`crate::codec::FastCgiCodec::max_buffered`].
+    pub fn max_decode_buffer_size(mut self, n: usize) -> Self {
+        self.max_decode_buffer_size = Some(n);
+        self
+    }
+
+    /// Flushes the transport after every record of a stream, e.g. stdin, instead of batching them.
+    ///
+    /// This lets the peer see streamed data promptly, which suits latency-sensitive workloads, at
+    /// the cost of more write calls. Disabled by default, which favors throughput.
+    pub fn flush_stream_records(mut self, flush: bool) -> Self {
+        self.flush_stream_records = flush;
+        self
+    }
+
+    /// Attaches the frames and bytes which were exchanged for a request to its response. See
+    /// [`crate::response::Response::get_metrics`].
+    ///
+    /// This helps tuning the frame size, padding and buffer limits based on real traffic. Only
+    /// applies to clients, and is disabled by default.
+    pub fn request_metrics(mut self, enable: bool) -> Self {
+        self.request_metrics = enable;
+        self
+    }
+
+    /// Sets the maximum content length of the frames of sent streams. See
+    /// [`crate::codec::FastCgiCodec::max_frame_size`].
+    pub fn max_frame_size(mut self, n: u16) -> Self {
+        self.max_frame_size = Some(n);
+        self
+    }
+
+    /// Sets a handler for management records, i.e. records with a request id of 0.
+    ///
+    /// This is an extension point for non-standard deployments, e.g. servers which send vendor
+    /// records with trailing metadata after a response on a `keep_conn` connection. Records which
+    /// arrive after a response has ended are read with [`crate::client::Client::recv_trailers`],
+    /// or otherwise handled while receiving the next response.
+    ///
+    /// Without a handler, management records are dropped, including unsolicited ones, such as a
+    /// `GetValuesResult` which was never requested.
+    pub fn management_record_handler(mut self, handler: RecordHandler) -> Self {
+        self.management_record_handler = Some(Handler(handler));
+        self
+    }
+
+    /// Sets a handler for application records of a custom type, i.e. outside of the types
+    /// defined by the spec.
+    ///
+    /// This allows a custom protocol to be layered on FastCGI. The handled records are passed
+    /// over by the parser, instead of failing the request with `UnexpectedRecordType`.
+    #[cfg(feature = "extensions")]
+    pub fn custom_record_handler(mut self, handler: RecordHandler) -> Self {
+        self.custom_record_handler = Some(Handler(handler));
+        self
+    }
+
+    /// Sets the time to wait for the first frame of a response, or of a request for servers.
+    ///
+    /// This guards against backends which are slow to start responding, independently of the
+    /// deadline of a request, and fails with `FirstFrameTimeout`.
+    pub fn first_frame_timeout(mut self, timeout: Duration) -> Self {
+        self.first_frame_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum time between two frames of the same response or request.
+    ///
+    /// This guards against backends which stall while streaming, and fails with `IdleTimeout`.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum time a write to the transport may take, including flushes.
+    ///
+    /// This guards against peers which stop reading, e.g. over a half-open connection, in which
+    /// case the socket buffer fills up and writes would otherwise hang forever. Fails with
+    /// `WriteTimeout`.
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = Some(timeout);
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            transport_buffer_size: DEFAULT_TRANSPORT_BUFFER_SIZE,
+            max_response_size: None,
+            max_stdout_size: None,
+            max_stderr_size: None,
+            max_stream_size: None,
+            unexpected_record_policy: UnexpectedRecordPolicy::default(),
+            require_end_of_stderr: false,
+            allow_empty_params: false,
+            max_content_length: None,
+            ignore_reserved: false,
+            max_decode_reservation: None,
+            max_decode_buffer_size: None,
+            flush_stream_records: false,
+            request_metrics: false,
+            max_frame_size: None,
+            management_record_handler: None,
+            #[cfg(feature = "extensions")]
+            custom_record_handler: None,
+            first_frame_timeout: None,
+            idle_timeout: None,
+            write_timeout: None,
+        }
+    }
+}
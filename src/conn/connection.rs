@@ -1,18 +1,23 @@
-use std::marker::PhantomData;
+use std::{future::Future, marker::PhantomData, time::Duration};
 
 use futures::{SinkExt, StreamExt};
-use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time,
+};
 use tokio_util::codec::Framed;
 
 use crate::{
-    codec::{DecodeCodecError, EncodeCodecError, FastCgiCodec, Frame},
+    codec::{DecodeCodecError, EncodeCodecError, FastCgiCodec, Frame, FrameStats},
     meta::{self, Meta},
     record::{
-        EncodeFrame, EncodeFrameError, EndOfStream, IntoStreamChunker, ProtocolStatus, Record,
+        EncodeChunk, EncodeFrame, EncodeFrameError, EndOfStream, Id, IntoStreamChunker,
+        ProtocolStatus, Record, RecordType, Standard, StreamChunker,
     },
 };
 
 use super::{
+    config::{Config, UnexpectedRecordPolicy},
     endpoint::Endpoint,
     state::{ParseError, State},
     stream::Stream,
@@ -21,18 +26,53 @@ use super::{
 #[derive(Debug)]
 pub(crate) struct Connection<T, P: Endpoint> {
     transport: Framed<T, FastCgiCodec>,
+    config: Config,
 
     // Currently supports simplexed connections only.
     streams: Option<Stream<P::State>>,
+    // Requests which were rejected because another request was in progress. Their remaining
+    // frames are skipped, until the id is reused by a new request.
+    rejected_ids: Vec<Id>,
+    // Rejected requests which haven't been answered yet.
+    pending_rejections: Vec<Id>,
     _marker: PhantomData<P>,
 }
 
-impl<T: AsyncRead + AsyncWrite, P: Endpoint> Connection<T, P> {
+impl<T, P: Endpoint> Connection<T, P> {
     pub fn new(transport: T) -> Self {
+        Self::with_config(transport, Config::default())
+    }
+
+    pub fn with_config(transport: T, config: Config) -> Self {
+        let mut codec = FastCgiCodec::new();
+
+        if config.ignore_reserved {
+            codec = codec.ignore_reserved();
+        }
+
+        if let Some(n) = config.max_decode_reservation {
+            codec = codec.max_reservation(n);
+        }
+
+        if let Some(n) = config.max_decode_buffer_size {
+            codec = codec.max_buffered(n);
+        }
+
+        if let Some(n) = config.max_frame_size {
+            codec = codec.max_frame_size(n);
+        }
+
+        if config.request_metrics {
+            codec = codec.count_frames();
+        }
+
         Self {
-            transport: Framed::new(transport, FastCgiCodec::new()),
+            transport: Framed::with_capacity(transport, codec, config.transport_buffer_size),
+            config,
 
             streams: None,
+            rejected_ids: Vec::new(),
+            pending_rejections: Vec::new(),
             _marker: PhantomData,
         }
     }
@@ -42,6 +82,84 @@ impl<T, P> Connection<T, P>
 where
     P: Endpoint,
 {
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn into_transport(self) -> T {
+        self.transport.into_inner()
+    }
+
+    /// Returns the request id of the open stream, if it received a frame.
+    pub fn stream_id(&self) -> Option<Id> {
+        self.streams.as_ref().and_then(Stream::id)
+    }
+
+    /// Returns the counters of the frames which were sent and received on the connection.
+    pub(crate) fn frame_stats(&self) -> (FrameStats, FrameStats) {
+        let codec = self.transport.codec();
+
+        (codec.encoded_stats(), codec.decoded_stats())
+    }
+
+    /// Returns the peak number of bytes which the defragmenters of the open stream held at once.
+    pub(crate) fn peak_buffered(&self) -> usize {
+        self.streams
+            .as_ref()
+            .map_or(0, |stream| stream.state().peak_buffered())
+    }
+
+    /// Opens a stream with the given parser state, instead of a default one on the first frame.
+    pub fn open_stream(&mut self, state: P::State) {
+        self.streams.replace(Stream::with_state(state));
+    }
+
+    /// Returns the ids of the requests which were rejected since the last call, because they
+    /// were received while another request was in progress.
+    pub(crate) fn take_rejections(&mut self) -> Vec<Id> {
+        std::mem::take(&mut self.pending_rejections)
+    }
+
+    /// Returns true if the frame belongs to another request than the one in progress, in which
+    /// case it must be skipped. Multiplexing isn't supported, so such requests are rejected.
+    fn is_concurrent_frame(&mut self, frame: &Frame) -> bool {
+        let is_begin_request = frame.record_type == RecordType::Standard(Standard::BeginRequest);
+        let rejected = self.rejected_ids.iter().position(|id| *id == frame.id);
+
+        match self.stream_id() {
+            Some(id) if id != frame.id => {
+                if is_begin_request && rejected.is_none() {
+                    self.rejected_ids.push(frame.id);
+                    self.pending_rejections.push(frame.id);
+                }
+
+                true
+            }
+            Some(_) => false,
+            None => match rejected {
+                // The id is reused by a new request.
+                Some(index) if is_begin_request => {
+                    self.rejected_ids.swap_remove(index);
+                    false
+                }
+                Some(_) => true,
+                None => false,
+            },
+        }
+    }
+
+    /// Closes the stream, and skips the remaining frames of its request until its id is reused
+    /// by a new request. Used when a request fails before all of its frames were received.
+    pub(crate) fn discard_stream(&mut self) {
+        if let Some(id) = self.stream_id() {
+            if !self.rejected_ids.contains(&id) {
+                self.rejected_ids.push(id);
+            }
+        }
+
+        self.close_stream();
+    }
+
     pub fn close_stream(&mut self) {
         // TODO
         self.streams.take();
@@ -62,18 +180,60 @@ where
     ) -> Option<Result<<P::State as State>::Output, ConnectionRecvError<<P::State as State>::Error>>>
     {
         loop {
-            let frame = match self.transport.next().await {
+            // The first frame of a stream sets its id.
+            let first_frame = self.stream_id().is_none();
+
+            let timeout = if first_frame {
+                self.config.first_frame_timeout
+            } else {
+                self.config.idle_timeout
+            };
+
+            let next = match timeout {
+                Some(timeout) => match time::timeout(timeout, self.transport.next()).await {
+                    Ok(next) => next,
+                    Err(_) if first_frame => {
+                        return Some(Err(ConnectionRecvError::FirstFrameTimeout))
+                    }
+                    Err(_) => return Some(Err(ConnectionRecvError::IdleTimeout)),
+                },
+                None => self.transport.next().await,
+            };
+
+            let frame = match next {
                 Some(Ok(frame)) => frame,
-                Some(Err(e)) => return Some(Err(e).map_err(ConnectionRecvError::from)),
+                Some(Err(e)) => return Some(Err(ConnectionRecvError::from(e))),
                 _ => return None,
             };
 
+            if frame.id != 0 && self.is_concurrent_frame(&frame) {
+                continue;
+            }
+
+            #[cfg(feature = "extensions")]
+            if let (Some(handler), RecordType::Custom(_)) =
+                (&self.config.custom_record_handler, frame.record_type)
+            {
+                handler.call(&frame);
+                continue;
+            }
+
             if frame.id == 0 {
-                // Handle management frames.
-                dbg!("Frame ignored: management records are currently not supported.");
+                // Management records are only surfaced through the handler, if one is set.
+                // Otherwise they're dropped, including unsolicited ones, as they never affect
+                // the stream in progress.
+                if let Some(handler) = &self.config.management_record_handler {
+                    handler.call(&frame);
+                }
             } else {
                 match self.poll_frame_inner(frame) {
                     Ok(Some(part)) => return Some(Ok(part)),
+                    Err(e)
+                        if self.config.unexpected_record_policy == UnexpectedRecordPolicy::Skip
+                            && e.unexpected_record_type().is_some() =>
+                    {
+                        // TODO, log this.
+                    }
                     Err(e) => return Some(Err(ConnectionRecvError::from(e))),
                     _ => {
                         // Received a stream chunk, continue polling for the rest.
@@ -83,6 +243,45 @@ where
         }
     }
 
+    /// Receives the next part, failing with `UnexpectedEndOfInput` if the transport closes
+    /// before it's complete.
+    pub async fn recv_part(
+        &mut self,
+    ) -> Result<<P::State as State>::Output, ConnectionRecvError<<P::State as State>::Error>> {
+        self.poll_frame()
+            .await
+            .unwrap_or(Err(ConnectionRecvError::UnexpectedEndOfInput))
+    }
+
+    /// Receives the management records which arrive between requests, and passes them to the
+    /// management record handler. Returns the number of records which were received once none
+    /// arrives within `wait`, or the transport closes.
+    pub(crate) async fn recv_trailers(
+        &mut self,
+        wait: Duration,
+    ) -> Result<usize, ConnectionRecvError<<P::State as State>::Error>> {
+        let mut received = 0;
+
+        loop {
+            let frame = match time::timeout(wait, self.transport.next()).await {
+                Ok(Some(Ok(frame))) => frame,
+                Ok(Some(Err(e))) => return Err(ConnectionRecvError::from(e)),
+                Ok(None) | Err(_) => return Ok(received),
+            };
+
+            // No request is in progress, so the records of a request can't affect any stream.
+            if frame.id != 0 {
+                continue;
+            }
+
+            if let Some(handler) = &self.config.management_record_handler {
+                handler.call(&frame);
+            }
+
+            received += 1;
+        }
+    }
+
     fn poll_frame_inner(
         &mut self,
         frame: Frame,
@@ -92,7 +291,7 @@ where
         } else {
             // Create a new stream state.
             // TODO: id must be available.
-            let mut stream = Stream::default();
+            let mut stream = Stream::with_state(P::State::from_config(&self.config));
             let record = stream.parse(frame)?;
 
             self.streams.replace(stream);
@@ -111,54 +310,98 @@ where
         record: Record<D>,
     ) -> Result<(), ConnectionSendError>
     where
-        D: EncodeFrame,
+        D: EncodeFrame + Meta<SentBy = P::SentBy>,
     {
-        self.transport
-            .feed(record)
-            .await
-            .map_err(ConnectionSendError::from)
+        write(self.config.write_timeout, self.transport.feed(record)).await
     }
 
+    /// Feeds a stream record, followed by the empty record which ends the stream. Returns the
+    /// number of bytes of the stream which were fed.
     pub(crate) async fn feed_stream<S>(
         &mut self,
         record: Record<S>,
-    ) -> Result<(), ConnectionSendError>
+    ) -> Result<u64, ConnectionSendError>
+    where
+        S: IntoStreamChunker + Meta<SentBy = P::SentBy>,
+    {
+        let record = self.feed_chunks(record.map_to_chunker()).await?;
+        let encoded_len = record.body.encoded_len();
+
+        write(
+            self.config.write_timeout,
+            self.transport.feed(record.map_to_empty()),
+        )
+        .await?;
+
+        Ok(encoded_len)
+    }
+
+    /// Feeds a stream record without ending the stream, so more records of the same stream can
+    /// follow. The stream must be ended with [`Connection::feed_empty`].
+    pub(crate) async fn feed_stream_chunk<S>(
+        &mut self,
+        record: Record<S>,
+    ) -> Result<u64, ConnectionSendError>
     where
-        S: IntoStreamChunker,
+        S: IntoStreamChunker + Meta<SentBy = P::SentBy>,
     {
-        let mut record = record.map_to_chunker();
+        let record = self.feed_chunks(record.map_to_chunker()).await?;
 
+        Ok(record.body.encoded_len())
+    }
+
+    async fn feed_chunks<S: EncodeChunk>(
+        &mut self,
+        mut record: Record<StreamChunker<S>>,
+    ) -> Result<Record<StreamChunker<S>>, ConnectionSendError> {
         loop {
             if record.body.is_empty() {
                 break;
             }
 
-            self.transport.feed(&mut record).await?;
-        }
+            write(self.config.write_timeout, self.transport.feed(&mut record)).await?;
 
-        let record = record.map_to_empty();
+            if self.config.flush_stream_records {
+                self.flush().await?;
+            }
+        }
 
-        self.transport
-            .feed(record)
-            .await
-            .map_err(ConnectionSendError::from)
+        Ok(record)
     }
 
-    pub(crate) async fn feed_empty<S: Meta<DataKind = meta::Stream>>(
+    pub(crate) async fn feed_empty<S: Meta<DataKind = meta::Stream, SentBy = P::SentBy>>(
         &mut self,
         record: Record<EndOfStream<S>>,
     ) -> Result<(), ConnectionSendError> {
-        self.transport
-            .feed(record)
-            .await
-            .map_err(ConnectionSendError::from)
+        write(self.config.write_timeout, self.transport.feed(record)).await
+    }
+
+    #[cfg(feature = "unstable")]
+    pub(crate) async fn feed_raw(&mut self, frame: Frame) -> Result<(), ConnectionSendError> {
+        write(self.config.write_timeout, self.transport.feed(frame)).await
     }
 
     pub(crate) async fn flush(&mut self) -> Result<(), ConnectionSendError> {
         // TODO: Figure out this necessary type annotation, currently set to () as it doesn't appear to do anything.
-        <Framed<T, FastCgiCodec> as SinkExt<()>>::flush(&mut self.transport)
+        write(
+            self.config.write_timeout,
+            <Framed<T, FastCgiCodec> as SinkExt<()>>::flush(&mut self.transport),
+        )
+        .await
+    }
+}
+
+/// Awaits a write to the transport, failing with `WriteTimeout` if it doesn't complete in time.
+async fn write(
+    timeout: Option<Duration>,
+    write: impl Future<Output = Result<(), EncodeCodecError>>,
+) -> Result<(), ConnectionSendError> {
+    match timeout {
+        Some(timeout) => time::timeout(timeout, write)
             .await
-            .map_err(ConnectionSendError::from)
+            .map_err(|_| ConnectionSendError::WriteTimeout)?
+            .map_err(ConnectionSendError::from),
+        None => write.await.map_err(ConnectionSendError::from),
     }
 }
 
@@ -166,6 +409,8 @@ where
 pub enum ConnectionSendError {
     EncodeCodecError(EncodeCodecError),
     EncodeFrameError(EncodeFrameError),
+    /// A write to the transport didn't complete within the configured timeout.
+    WriteTimeout,
 }
 
 impl From<EncodeCodecError> for ConnectionSendError {
@@ -186,6 +431,11 @@ pub enum ConnectionRecvError<T: ParseError> {
     ParserError(T),
     ProtocolStatus(ProtocolStatus),
     UnexpectedEndOfInput,
+    /// The first frame wasn't received within the configured timeout.
+    FirstFrameTimeout,
+    /// No frame was received within the configured idle timeout.
+    IdleTimeout,
+    SinkError(Box<dyn std::error::Error + Send + Sync>),
     StdIoError(std::io::Error),
 }
 
@@ -212,3 +462,161 @@ impl<T: ParseError> From<std::io::Error> for ConnectionRecvError<T> {
         ConnectionRecvError::StdIoError(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
+
+    use bytes::Bytes;
+    use futures::executor::block_on;
+
+    use crate::{
+        conn::{endpoint, ParseResponseError},
+        record::{ByteSlice, IntoRecord, RecordType, Stdin},
+        response::Part,
+    };
+
+    use super::*;
+
+    fn frame(record_type: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![1, record_type, 0, 1, 0, payload.len() as u8, 0, 0];
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn vendor_record_then_stdout() -> Vec<u8> {
+        [frame(20, b"vendor"), frame(6, b"out"), frame(6, b"")].concat()
+    }
+
+    #[test]
+    fn unexpected_record_is_rejected_by_default() {
+        let src = vendor_record_then_stdout();
+        let mut connection = Connection::<_, endpoint::Client>::new(&src[..]);
+
+        let result = block_on(connection.poll_frame());
+
+        assert!(matches!(
+            result,
+            Some(Err(ConnectionRecvError::ParserError(
+                ParseResponseError::UnexpectedRecordType(RecordType::Custom(_))
+            )))
+        ));
+    }
+
+    #[test]
+    fn unexpected_record_is_skipped() {
+        let src = vendor_record_then_stdout();
+        let config = Config::new().unexpected_record_policy(UnexpectedRecordPolicy::Skip);
+        let mut connection = Connection::<_, endpoint::Client>::with_config(&src[..], config);
+
+        let result = block_on(connection.poll_frame());
+
+        let Some(Ok(Part::Stdout(Some(stdout)))) = result else {
+            panic!("expected Stdout, got {:?}", result);
+        };
+        assert_eq!(&stdout[..], b"out");
+    }
+
+    #[test]
+    fn management_records_are_handled() {
+        let mut src = frame(6, b"out");
+        src.extend(frame(6, b""));
+        // A vendor record after the response ended.
+        src.extend([1, 20, 0, 0, 0, 3, 0, 0]);
+        src.extend(b"end");
+
+        let handled = Arc::new(AtomicUsize::new(0));
+        let counter = handled.clone();
+
+        let config = Config::new().management_record_handler(Arc::new(move |frame: &Frame| {
+            assert_eq!(frame.id, 0);
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        let mut connection = Connection::<_, endpoint::Client>::with_config(&src[..], config);
+
+        let result = block_on(connection.poll_frame());
+        assert!(matches!(result, Some(Ok(Part::Stdout(Some(_))))));
+        assert_eq!(handled.load(Ordering::SeqCst), 0);
+
+        assert!(block_on(connection.poll_frame()).is_none());
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "extensions")]
+    #[test]
+    fn custom_record_is_handled() {
+        let handled = Arc::new(AtomicUsize::new(0));
+        let counter = handled.clone();
+
+        let config = Config::new().custom_record_handler(Arc::new(move |frame: &Frame| {
+            let (_, record_type, payload) = frame.as_parts();
+
+            assert_eq!(u8::from(record_type), 20);
+            assert_eq!(&payload[..], b"vendor");
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let src = vendor_record_then_stdout();
+        let mut connection = Connection::<_, endpoint::Client>::with_config(&src[..], config);
+
+        let result = block_on(connection.poll_frame());
+
+        assert!(matches!(result, Some(Ok(Part::Stdout(Some(_))))));
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+    }
+
+    /// A transport which counts the number of times it was flushed.
+    #[derive(Default)]
+    struct FlushCounter {
+        flushes: usize,
+    }
+
+    impl AsyncWrite for FlushCounter {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            self.flushes += 1;
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn flushes_while_streaming(config: Config) -> usize {
+        let mut connection =
+            Connection::<_, endpoint::Client>::with_config(FlushCounter::default(), config);
+
+        // Requires 4 records of the maximum payload size.
+        let stdin = Stdin(ByteSlice::new(Bytes::from(vec![0; 200_000])).unwrap());
+        block_on(connection.feed_stream(stdin.into_record(1))).unwrap();
+
+        connection.into_transport().flushes
+    }
+
+    #[test]
+    fn stream_records_are_flushed() {
+        let batched = flushes_while_streaming(Config::new());
+        let flushed = flushes_while_streaming(Config::new().flush_stream_records(true));
+
+        // The transport is flushed at least once for every record, while batching only flushes
+        // when the write buffer is full.
+        assert!(flushed >= 4);
+        assert!(batched < flushed);
+    }
+}
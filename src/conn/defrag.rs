@@ -0,0 +1,146 @@
+use std::fmt;
+
+use bytes::{BufMut, BytesMut};
+
+/// The maximum size of a single stream which is reassembled, unless configured otherwise.
+pub(crate) const DEFAULT_MAX_STREAM_SIZE: usize = 0x4000000; // 64 MB
+
+/// Reassembles the payloads of stream records which are split over multiple frames.
+///
+/// The parsers of the client and server are generic over this trait, which allows alternative
+/// strategies to be plugged in, e.g. one which enforces different limits or reuses allocations.
+/// `Defrag` is the default implementation.
+///
+/// Only in-memory strategies are covered, as a reassembled stream is returned as one `BytesMut`
+/// and decoded into an in-memory record. A stdout which doesn't fit in memory can be consumed as
+/// it arrives with [`crate::client::Client::send_streaming`] instead.
+pub trait Defragmenter: Default + fmt::Debug {
+    /// Stores the payload of a received stream frame.
+    fn insert_payload(&mut self, payload: BytesMut) -> Result<(), ExceededMaximumStreamSize>;
+
+    /// Returns the reassembled stream, or None if no payloads were inserted.
+    ///
+    /// The defragmenter must be ready to reassemble the next stream afterwards.
+    fn handle_end_of_stream(&mut self) -> Option<BytesMut>;
+
+    /// Sets the maximum size of a single reassembled stream. Used to apply
+    /// [`crate::conn::Config::max_stream_size`].
+    fn with_max_payload_size(self, n: usize) -> Self;
+
+    /// Returns the number of bytes which are currently stored. Only used for metrics, and 0 for
+    /// defragmenters which don't track it.
+    fn buffered(&self) -> usize {
+        0
+    }
+}
+
+/// Temporarily stores received stream frames of the same record type.
+///
+/// The default maximum size of the payload is 64MB (1024 full frames). This can be adjusted
+/// with `with_max_payload_size`, or for a client or server with
+/// [`crate::conn::Config::max_stream_size`]. As the project is at an early stage, it's recommended to
+/// manually set the maximum to avoid unexpected changes to the maximum payload size in the
+/// future.
+#[derive(Debug)]
+pub struct Defrag {
+    payloads: Vec<BytesMut>,
+    max_total_payload: usize,
+    current_total_payload: usize,
+}
+
+impl Defrag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Defragmenter for Defrag {
+    fn insert_payload(&mut self, payload: BytesMut) -> Result<(), ExceededMaximumStreamSize> {
+        let new_size = self.current_total_payload + payload.len();
+
+        if self.max_total_payload < new_size {
+            Err(ExceededMaximumStreamSize(new_size, self.max_total_payload))?;
+        }
+
+        self.payloads.push(payload);
+        self.current_total_payload = new_size;
+
+        Ok(())
+    }
+
+    fn handle_end_of_stream(&mut self) -> Option<BytesMut> {
+        if self.payloads.is_empty() {
+            return None;
+        }
+
+        // Should this much space be reserved beforehand?
+        // The frames drain iter could be chunked, with memory being reserved for each chunk instead.
+        let mut buffer = BytesMut::with_capacity(self.current_total_payload);
+
+        for payload in self.payloads.drain(..) {
+            buffer.put(payload);
+        }
+
+        self.current_total_payload = 0;
+
+        Some(buffer)
+    }
+
+    fn with_max_payload_size(mut self, n: usize) -> Self {
+        self.max_total_payload = n;
+        self
+    }
+
+    fn buffered(&self) -> usize {
+        self.current_total_payload
+    }
+}
+
+impl Default for Defrag {
+    fn default() -> Self {
+        Self {
+            payloads: Vec::new(),
+            max_total_payload: DEFAULT_MAX_STREAM_SIZE,
+            current_total_payload: 0,
+        }
+    }
+}
+
+pub struct ExceededMaximumStreamSize(pub usize, pub usize);
+
+impl std::fmt::Debug for ExceededMaximumStreamSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The stream has exceeded it's maximum allowed size [{} < {}].",
+            self.0, self.1
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassemble_payloads() {
+        let mut defrag = Defrag::new();
+
+        defrag.insert_payload(BytesMut::from(&b"ab"[..])).unwrap();
+        defrag.insert_payload(BytesMut::from(&b"cd"[..])).unwrap();
+
+        assert_eq!(&defrag.handle_end_of_stream().unwrap()[..], b"abcd");
+        assert!(defrag.handle_end_of_stream().is_none());
+    }
+
+    #[test]
+    fn maximum_size_is_per_stream() {
+        let mut defrag = Defrag::new().with_max_payload_size(4);
+
+        defrag.insert_payload(BytesMut::from(&b"abcd"[..])).unwrap();
+        defrag.handle_end_of_stream().unwrap();
+
+        defrag.insert_payload(BytesMut::from(&b"efgh"[..])).unwrap();
+        assert!(defrag.insert_payload(BytesMut::from(&b"i"[..])).is_err());
+    }
+}
@@ -1,21 +1,32 @@
 use core::fmt;
+use std::marker::PhantomData;
 
-use super::state;
+use crate::meta;
+
+use super::{
+    defrag::{Defrag, Defragmenter},
+    state,
+};
 
 pub(crate) trait Endpoint {
     type State: state::State + fmt::Debug;
+
+    /// The records which the endpoint is allowed to send.
+    type SentBy: meta::SentBy;
 }
 
 #[derive(Debug)]
-pub(crate) enum Client {}
+pub(crate) struct Client<D: Defragmenter = Defrag>(PhantomData<D>);
 
 #[derive(Debug)]
-pub(crate) enum Server {}
+pub(crate) struct Server<D: Defragmenter = Defrag>(PhantomData<D>);
 
-impl Endpoint for Client {
-    type State = state::client::State;
+impl<D: Defragmenter> Endpoint for Client<D> {
+    type State = state::client::State<D>;
+    type SentBy = meta::Client;
 }
 
-impl Endpoint for Server {
-    type State = state::server::State;
+impl<D: Defragmenter> Endpoint for Server<D> {
+    type State = state::server::State<D>;
+    type SentBy = meta::Server;
 }
@@ -1,6 +1,11 @@
+pub(crate) mod config;
 pub(crate) mod connection;
+pub(crate) mod defrag;
 pub(crate) mod endpoint;
 pub(crate) mod state;
 pub(crate) mod stream;
 
+pub use config::{Config, RecordHandler, UnexpectedRecordPolicy, DEFAULT_TRANSPORT_BUFFER_SIZE};
+pub use connection::{ConnectionRecvError, ConnectionSendError};
+pub use defrag::{Defrag, Defragmenter, ExceededMaximumStreamSize};
 pub use state::{client::ParseResponseError, server::ParseRequestError, ParseError};
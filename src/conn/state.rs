@@ -1,25 +1,36 @@
-use bytes::{BufMut, BytesMut};
+use crate::{codec::Frame, record::RecordType, request, response};
 
-use crate::{codec::Frame, request, response};
+use super::{config::Config, defrag::Defragmenter};
 
 pub(crate) trait State: Default {
     type Transition;
     type Output;
     type Error: ParseError;
 
+    fn from_config(config: &Config) -> Self;
+
     fn parse_transition(frame: Frame) -> Result<Self::Transition, Self::Error>;
 
     fn parse_frame(
         &mut self,
         transition: Self::Transition,
     ) -> Result<Option<Self::Output>, Self::Error>;
+
+    /// Returns the peak number of bytes which the defragmenters held at once.
+    fn peak_buffered(&self) -> usize {
+        0
+    }
 }
 
-impl State for client::State {
+impl<D: Defragmenter> State for client::State<D> {
     type Transition = client::Transition;
     type Output = response::Part;
     type Error = client::ParseResponseError;
 
+    fn from_config(config: &Config) -> Self {
+        Self::from_config(config)
+    }
+
     fn parse_transition(frame: Frame) -> Result<Self::Transition, Self::Error> {
         Self::Transition::parse(frame)
     }
@@ -30,15 +41,23 @@ impl State for client::State {
     ) -> Result<Option<Self::Output>, Self::Error> {
         self.parse_frame(transition)
     }
+
+    fn peak_buffered(&self) -> usize {
+        self.peak_buffered
+    }
 }
 
-impl State for server::State {
+impl<D: Defragmenter> State for server::State<D> {
     type Transition = server::Transition;
     type Output = request::Part;
     type Error = server::ParseRequestError;
 
+    fn from_config(config: &Config) -> Self {
+        Self::from_config(config)
+    }
+
     fn parse_transition(frame: Frame) -> Result<Self::Transition, Self::Error> {
-        Ok(Self::Transition::parse(frame))
+        Self::Transition::parse(frame)
     }
 
     fn parse_frame(
@@ -49,88 +68,37 @@ impl State for server::State {
     }
 }
 
-/// Temporarily stores received stream frames of the same record type.
-///
-/// The default maximum size of the payload is 64MB (1024 full frames). This can be adjusted
-/// with `with_max_payload_size`. As the project is at an early stage, it's recommended to
-/// manually set the maximum to avoid unexpected changes to the maximum payload size in the
-/// future.
-#[derive(Debug)]
-pub(crate) struct Defrag {
-    payloads: Vec<BytesMut>,
-    max_total_payload: usize,
-    current_total_payload: usize,
-}
-
-impl Defrag {
-    pub(crate) fn new() -> Self {
-        Self::default()
-    }
-
-    pub(crate) fn with_max_payload_size(mut self, n: usize) -> Self {
-        self.max_total_payload = n;
-        self
+/// Creates a defragmenter, limited to the maximum stream size of the config if one is set.
+fn defrag_from_config<D: Defragmenter>(config: &Config) -> D {
+    match config.max_stream_size {
+        Some(n) => D::default().with_max_payload_size(n),
+        None => D::default(),
     }
+}
 
-    pub(crate) fn insert_payload(
-        &mut self,
-        payload: BytesMut,
-    ) -> Result<(), ExceededMaximumStreamSize> {
-        let new_size = self.current_total_payload + payload.len();
-
-        if self.max_total_payload < new_size {
-            Err(ExceededMaximumStreamSize(new_size, self.max_total_payload))?;
-        }
-
-        self.payloads.push(payload);
-        self.current_total_payload = new_size;
-
-        Ok(())
-    }
-
-    pub(crate) fn handle_end_of_stream(&mut self) -> Option<BytesMut> {
-        if self.payloads.is_empty() {
-            return None;
-        }
-
-        // Should this much space be reserved beforehand?
-        // The frames drain iter could be chunked, with memory being reserved for each chunk instead.
-        let mut buffer = BytesMut::with_capacity(self.current_total_payload);
-
-        for payload in self.payloads.drain(..) {
-            buffer.put(payload);
-        }
-
-        Some(buffer)
-    }
+pub trait ParseError {
+    /// Returns the record type if the error was caused by a record of an unexpected type.
+    fn unexpected_record_type(&self) -> Option<RecordType>;
 }
 
-impl Default for Defrag {
-    fn default() -> Self {
-        Self {
-            payloads: Vec::new(),
-            max_total_payload: 0x4000000, // 64 MB
-            current_total_payload: 0,
+impl ParseError for client::ParseResponseError {
+    fn unexpected_record_type(&self) -> Option<RecordType> {
+        match self {
+            client::ParseResponseError::UnexpectedRecordType(record_type) => Some(*record_type),
+            _ => None,
         }
     }
 }
 
-pub struct ExceededMaximumStreamSize(usize, usize);
-
-impl std::fmt::Debug for ExceededMaximumStreamSize {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "The stream has exceeded it's maximum allowed size [{} < {}].",
-            self.0, self.1
-        )
+impl ParseError for server::ParseRequestError {
+    fn unexpected_record_type(&self) -> Option<RecordType> {
+        match self {
+            server::ParseRequestError::UnexpectedRecordType(record_type) => Some(*record_type),
+            _ => None,
+        }
     }
 }
 
-pub trait ParseError {}
-impl ParseError for client::ParseResponseError {}
-impl ParseError for server::ParseRequestError {}
-
 pub mod client {
     use bytes::BytesMut;
 
@@ -140,7 +108,10 @@ pub mod client {
         response::Part,
     };
 
-    use super::{Defrag, ExceededMaximumStreamSize};
+    use crate::conn::{
+        config::Config,
+        defrag::{Defrag, Defragmenter, ExceededMaximumStreamSize},
+    };
 
     type ParseResult<T> = Result<T, ParseResponseError>;
 
@@ -180,7 +151,10 @@ pub mod client {
         pub(crate) fn parse(frame: Frame) -> ParseResult<Transition> {
             let (id, record_type, payload) = frame.into_parts();
 
-            assert!(id > 0);
+            // Management records are routed by the connection, but frames are untrusted input.
+            if id == 0 {
+                return Err(ParseResponseError::UnexpectedManagementRecord);
+            }
 
             let transition = match (record_type, payload.is_empty()) {
                 (RecordType::Standard(Standard::Stdout), false) => Transition::ParseStdout(payload),
@@ -208,24 +182,100 @@ pub mod client {
     }
 
     #[derive(Debug, Default)]
-    pub(crate) struct State {
+    pub(crate) struct State<D: Defragmenter = Defrag> {
         inner: Inner,
 
         // stdout and stderr can be interleaved.
-        stdout_defrag: Defrag,
-        stderr_defrag: Defrag,
+        stdout_defrag: D,
+        stderr_defrag: D,
+
+        // The sizes of the streams so far, which are limited independently of the defragmenters.
+        stdout_size: usize,
+        stderr_size: usize,
+        max_stdout_size: Option<usize>,
+        max_stderr_size: Option<usize>,
+
+        // The most bytes the defragmenters held at once, as stdout and stderr are interleaved.
+        pub(super) peak_buffered: usize,
+
+        // Yield stdout payloads as they're received instead of reassembling them.
+        stream_stdout: bool,
+        require_end_of_stderr: bool,
     }
 
-    impl State {
+    impl<D: Defragmenter> State<D> {
         pub(crate) fn new() -> Self {
             Self {
                 inner: Inner::Std {
                     out: StreamState::Init,
                     err: StreamState::Init,
                 },
-                stdout_defrag: Defrag::default(),
-                stderr_defrag: Defrag::default(),
+                stdout_defrag: D::default(),
+                stderr_defrag: D::default(),
+                stdout_size: 0,
+                stderr_size: 0,
+                max_stdout_size: None,
+                max_stderr_size: None,
+                peak_buffered: 0,
+                stream_stdout: false,
+                require_end_of_stderr: false,
+            }
+        }
+
+        pub(crate) fn from_config(config: &Config) -> Self {
+            Self {
+                stdout_defrag: super::defrag_from_config(config),
+                stderr_defrag: super::defrag_from_config(config),
+                require_end_of_stderr: config.require_end_of_stderr,
+                max_stdout_size: config.max_stdout_size,
+                max_stderr_size: config.max_stderr_size,
+                ..Self::new()
+            }
+        }
+
+        pub(crate) fn with_stdout_streaming(mut self) -> Self {
+            self.stream_stdout = true;
+            self
+        }
+
+        fn insert_stdout(&mut self, payload: BytesMut) -> ParseResult<Option<Part>> {
+            self.stdout_size += payload.len();
+
+            if let Some(max) = self.max_stdout_size.filter(|max| self.stdout_size > *max) {
+                return Err(ParseResponseError::ExceededMaximumStdoutSize(
+                    ExceededMaximumStreamSize(self.stdout_size, max),
+                ));
+            }
+
+            if self.stream_stdout {
+                return Ok(Some(Part::StdoutChunk(payload.freeze())));
+            }
+
+            self.stdout_defrag.insert_payload(payload)?;
+            self.update_peak_buffered();
+
+            Ok(None)
+        }
+
+        fn insert_stderr(&mut self, payload: BytesMut) -> ParseResult<()> {
+            self.stderr_size += payload.len();
+
+            if let Some(max) = self.max_stderr_size.filter(|max| self.stderr_size > *max) {
+                return Err(ParseResponseError::ExceededMaximumStderrSize(
+                    ExceededMaximumStreamSize(self.stderr_size, max),
+                ));
             }
+
+            self.stderr_defrag.insert_payload(payload)?;
+            self.update_peak_buffered();
+
+            Ok(())
+        }
+
+        fn update_peak_buffered(&mut self) {
+            let buffered = self.stdout_defrag.buffered() + self.stderr_defrag.buffered();
+
+            self.peak_buffered = self.peak_buffered.max(buffered);
         }
 
         /// Return a part when it can be fully constructed, otherwise returns None.
@@ -239,14 +289,14 @@ pub mod client {
                     },
                     Transition::ParseStdout(payload),
                 ) => {
-                    self.stdout_defrag.insert_payload(payload)?;
+                    let part = self.insert_stdout(payload)?;
 
                     self.inner = Inner::Std {
                         out: StreamState::Started,
                         err,
                     };
 
-                    None
+                    part
                 }
                 (
                     Inner::Std {
@@ -254,10 +304,7 @@ pub mod client {
                         ..
                     },
                     Transition::ParseStdout(payload),
-                ) => {
-                    self.stdout_defrag.insert_payload(payload)?;
-                    None
-                }
+                ) => self.insert_stdout(payload)?,
 
                 // EndOfStdout
                 (
@@ -303,7 +350,7 @@ pub mod client {
                     },
                     Transition::ParseStderr(payload),
                 ) => {
-                    self.stderr_defrag.insert_payload(payload)?;
+                    self.insert_stderr(payload)?;
 
                     self.inner = Inner::Std {
                         err: StreamState::Started,
@@ -319,7 +366,7 @@ pub mod client {
                     },
                     Transition::ParseStderr(payload),
                 ) => {
-                    self.stderr_defrag.insert_payload(payload)?;
+                    self.insert_stderr(payload)?;
                     None
                 }
 
@@ -361,18 +408,35 @@ pub mod client {
                 }
 
                 // EndRequest
-                (
-                    Inner::Std {
-                        out: StreamState::Ended,
-                        err: StreamState::Init | StreamState::Ended,
-                    },
-                    Transition::ParseEndRequest(payload),
-                ) => {
+                // A server may end the request at any point, e.g. when rejecting it with
+                // `CantMpxConn` before sending any stdout. Streams which weren't ended yet are
+                // finalized with the payloads received so far.
+                (Inner::Std { err, .. }, Transition::ParseEndRequest(payload)) => {
+                    if self.require_end_of_stderr && !matches!(err, StreamState::Ended) {
+                        return Err(ParseResponseError::MissingEndOfStderr);
+                    }
+
                     let end_request = EndRequest::decode_frame(payload)?;
 
+                    let stdout = self
+                        .stdout_defrag
+                        .handle_end_of_stream()
+                        .map(Stdout::decode_frame)
+                        .transpose()?;
+
+                    let stderr = self
+                        .stderr_defrag
+                        .handle_end_of_stream()
+                        .map(Stderr::decode_frame)
+                        .transpose()?;
+
                     self.inner = Inner::Finished;
 
-                    Some(Part::from(end_request))
+                    if stdout.is_none() && stderr.is_none() {
+                        Some(Part::from(end_request))
+                    } else {
+                        Some(Part::from((stdout, stderr, end_request)))
+                    }
                 }
 
                 // Invalid state
@@ -388,9 +452,17 @@ pub mod client {
         InvalidState,
         UnexpectedRecordType(RecordType),
 
+        // Specific errors.
+        MissingEndOfStderr,
+        UnexpectedManagementRecord,
+
         // Defrag
         ExceededMaximumStreamSize(ExceededMaximumStreamSize),
 
+        // Configured limits
+        ExceededMaximumStdoutSize(ExceededMaximumStreamSize),
+        ExceededMaximumStderrSize(ExceededMaximumStreamSize),
+
         DecodeFrameError(DecodeFrameError),
         StdIoError(std::io::Error),
     }
@@ -415,16 +487,21 @@ pub mod client {
 }
 
 pub mod server {
+    use bytes::BytesMut;
+
     use crate::{
         codec::Frame,
         record::{
-            begin_request::Role, BeginRequest, Data, DecodeFrame, DecodeFrameError, Params,
-            RecordType, Standard, Stdin,
+            begin_request::Role, BeginRequest, Data, DecodeFrame, DecodeFrameError,
+            NameValuePairDecoder, NameValuePairs, Params, RecordType, Standard, Stdin,
         },
         request::Part,
     };
 
-    use super::{Defrag, ExceededMaximumStreamSize};
+    use crate::conn::{
+        config::Config,
+        defrag::{Defrag, Defragmenter, ExceededMaximumStreamSize, DEFAULT_MAX_STREAM_SIZE},
+    };
 
     type ParseResult<T> = Result<T, ParseRequestError>;
 
@@ -453,34 +530,152 @@ pub mod server {
     }
 
     impl Transition {
-        pub(crate) fn parse(frame: Frame) -> Transition {
+        pub(crate) fn parse(frame: Frame) -> ParseResult<Transition> {
             let (id, record_type, payload) = frame.as_parts();
 
-            assert!(id > 0);
+            // Management records are routed by the connection, but frames are untrusted input.
+            if id == 0 {
+                return Err(ParseRequestError::UnexpectedManagementRecord);
+            }
 
-            if !payload.is_empty() {
+            let transition = if !payload.is_empty() {
                 Transition::Parse(frame)
             } else if record_type == Standard::AbortRequest {
                 Transition::Abort
             } else {
                 Transition::EndOfStream(record_type)
-            }
+            };
+
+            Ok(transition)
         }
     }
 
-    #[derive(Debug, Default)]
-    pub(crate) struct State {
+    #[derive(Debug)]
+    pub(crate) struct State<D: Defragmenter = Defrag> {
         inner: Inner,
         role: Option<Role>,
-        defrag: Defrag,
+        defrag: D,
+
+        allow_empty_params: bool,
+        max_content_length: Option<u64>,
+
+        // Yield params as soon as each pair is received, instead of after the whole stream.
+        stream_params: bool,
+        params_decoder: NameValuePairDecoder,
+        params: NameValuePairs,
+        // Streamed params bypass the defragmenter, so their size is limited here instead.
+        params_size: usize,
+        max_params_size: usize,
     }
 
-    impl State {
+    impl<D: Defragmenter> Default for State<D> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<D: Defragmenter> State<D> {
         pub(crate) fn new() -> Self {
             State {
                 inner: Inner::BeginRequest,
                 role: None,
-                defrag: Defrag::new(),
+                defrag: D::default(),
+                allow_empty_params: false,
+                max_content_length: None,
+                stream_params: false,
+                params_decoder: NameValuePairDecoder::new(),
+                params: NameValuePairs::new(),
+                params_size: 0,
+                max_params_size: DEFAULT_MAX_STREAM_SIZE,
+            }
+        }
+
+        pub(crate) fn from_config(config: &Config) -> Self {
+            Self {
+                defrag: super::defrag_from_config(config),
+                allow_empty_params: config.allow_empty_params,
+                max_content_length: config.max_content_length,
+                max_params_size: config.max_stream_size.unwrap_or(DEFAULT_MAX_STREAM_SIZE),
+                ..Self::new()
+            }
+        }
+
+        pub(crate) fn with_params_streaming(mut self) -> Self {
+            self.stream_params = true;
+            self
+        }
+
+        fn insert_params(&mut self, payload: BytesMut) -> ParseResult<Option<Part>> {
+            if !self.stream_params {
+                self.defrag.insert_payload(payload)?;
+
+                return Ok(None);
+            }
+
+            self.params_size += payload.len();
+
+            if self.params_size > self.max_params_size {
+                Err(ExceededMaximumStreamSize(
+                    self.params_size,
+                    self.max_params_size,
+                ))?;
+            }
+
+            self.params_decoder.insert_payload(payload);
+
+            let mut chunk = NameValuePairs::new();
+
+            while let Some(nvp) = self.params_decoder.decode_next()? {
+                if !Params::validate(&nvp) {
+                    return Err(DecodeFrameError::CorruptedFrame.into());
+                }
+
+                chunk = chunk.insert_nvp(nvp);
+            }
+
+            if chunk.as_ref().is_empty() {
+                return Ok(None);
+            }
+
+            self.params.as_mut().extend(chunk.as_ref().iter().cloned());
+
+            Ok(Some(Part::ParamsChunk(chunk)))
+        }
+
+        fn end_params(&mut self) -> ParseResult<Option<Params>> {
+            if !self.stream_params {
+                return Ok(self
+                    .defrag
+                    .handle_end_of_stream()
+                    .map(Params::decode_frame)
+                    .transpose()?);
+            }
+
+            self.params_decoder.finish()?;
+            self.params_size = 0;
+
+            let params = std::mem::take(&mut self.params);
+
+            Ok((!params.as_ref().is_empty()).then(|| Params::new(params)))
+        }
+
+        /// Rejects a request which declares a larger body than allowed, before any of its stdin
+        /// is buffered.
+        fn validate_content_length(&self, params: &Params) -> ParseResult<()> {
+            let Some(max) = self.max_content_length else {
+                return Ok(());
+            };
+
+            let length = params
+                .get("CONTENT_LENGTH")
+                .and_then(|value| std::str::from_utf8(value).ok())
+                .and_then(|value| value.parse::<u64>().ok());
+
+            match length {
+                Some(length) if length > max => {
+                    Err(ParseRequestError::ExceededMaximumContentLength(length))
+                }
+                _ => Ok(()),
             }
         }
 
@@ -505,26 +700,24 @@ pub mod server {
 
                     validate_record_type(record_type, Standard::Params)?;
 
-                    self.defrag.insert_payload(payload)?;
-
-                    None
+                    self.insert_params(payload)?
                 }
                 (Inner::Params, Transition::EndOfStream(record_type)) => {
                     validate_record_type(record_type, Standard::Params)?;
 
-                    let params = self
-                        .defrag
-                        .handle_end_of_stream()
-                        .map(Params::decode_frame)
-                        .transpose()?;
+                    let params = self.end_params()?;
 
                     self.inner = Inner::Stdin;
 
-                    if params.is_none() {
-                        return Err(ParseRequestError::ParamsMustBeLargerThanZero);
-                    }
+                    let params = match params {
+                        Some(params) => params,
+                        None if self.allow_empty_params => Params::new(NameValuePairs::new()),
+                        None => return Err(ParseRequestError::ParamsMustBeLargerThanZero),
+                    };
 
-                    params.map(Part::from)
+                    self.validate_content_length(&params)?;
+
+                    Some(Part::from(params))
                 }
 
                 (Inner::Stdin, Transition::Parse(frame)) => {
@@ -613,8 +806,11 @@ pub mod server {
 
         // Specific errors.
         UnexpectedAbortRequest,
+        UnexpectedManagementRecord,
         ParamsMustBeLargerThanZero,
         DataIsRequiredForFilterApplications,
+        /// The declared `CONTENT_LENGTH` exceeds the configured maximum.
+        ExceededMaximumContentLength(u64),
 
         // Defrag
         ExceededMaximumStreamSize(ExceededMaximumStreamSize),
@@ -641,3 +837,327 @@ pub mod server {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::Decoder;
+
+    use crate::{
+        codec::{FastCgiCodec, Frame},
+        conn::{config::Config, defrag::Defrag, stream::Stream},
+        record::{
+            begin_request::Role, BeginRequest, EndRequest, NameValuePair, NameValuePairs, Params,
+            ProtocolStatus, Standard,
+        },
+        request, response,
+    };
+
+    use super::*;
+
+    fn frame(record_type: Standard, payload: &[u8]) -> Frame {
+        Frame::new(1, record_type.into(), BytesMut::from(payload))
+    }
+
+    fn end_request(protocol_status: ProtocolStatus) -> Frame {
+        let mut payload = BytesMut::new();
+        EndRequest::new(0, protocol_status)
+            .encode(&mut payload)
+            .unwrap();
+
+        Frame::new(1, Standard::EndRequest.into(), payload)
+    }
+
+    fn begin_request(role: Role) -> Frame {
+        let mut payload = BytesMut::new();
+        BeginRequest::from_parts(role, false)
+            .encode(&mut payload)
+            .unwrap();
+
+        Frame::new(1, Standard::BeginRequest.into(), payload)
+    }
+
+    #[test]
+    fn params_streamed_across_frames() {
+        let mut stream = Stream::with_state(server::State::<Defrag>::new().with_params_streaming());
+
+        stream.parse(begin_request(Role::Responder)).unwrap();
+
+        // The first pair is split in the middle of its name.
+        let part = stream
+            .parse(frame(Standard::Params, b"\x0e\x03REQUEST"))
+            .unwrap();
+        assert!(part.is_none());
+
+        let part = stream
+            .parse(frame(Standard::Params, b"_METHODGET\x01\x01ab\x01"))
+            .unwrap();
+        let Some(request::Part::ParamsChunk(nvps)) = part else {
+            panic!("expected a ParamsChunk, got {:?}", part);
+        };
+        assert_eq!(nvps.as_ref().len(), 2);
+        assert_eq!(nvps.as_ref()[0].name.inner(), b"REQUEST_METHOD");
+
+        let part = stream.parse(frame(Standard::Params, b"\x01cd")).unwrap();
+        assert!(matches!(part, Some(request::Part::ParamsChunk(_))));
+
+        let part = stream.parse(frame(Standard::Params, b"")).unwrap();
+        let Some(request::Part::Params(params)) = part else {
+            panic!("expected Params, got {:?}", part);
+        };
+        let expected = NameValuePairs::new()
+            .insert_nvp(NameValuePair::new("REQUEST_METHOD", "GET").unwrap())
+            .insert_nvp(NameValuePair::new("a", "b").unwrap())
+            .insert_nvp(NameValuePair::new("c", "d").unwrap());
+        assert_eq!(params, Params::new(expected));
+    }
+
+    #[test]
+    fn empty_params() {
+        let mut stream = Stream::<server::State>::default();
+
+        stream.parse(begin_request(Role::Authorizer)).unwrap();
+
+        let result = stream.parse(frame(Standard::Params, b""));
+        assert!(matches!(
+            result,
+            Err(server::ParseRequestError::ParamsMustBeLargerThanZero)
+        ));
+
+        let config = Config::new().allow_empty_params(true);
+        let mut stream = Stream::with_state(server::State::<Defrag>::from_config(&config));
+
+        stream.parse(begin_request(Role::Authorizer)).unwrap();
+
+        let part = stream.parse(frame(Standard::Params, b"")).unwrap();
+        let Some(request::Part::Params(params)) = part else {
+            panic!("expected Params, got {:?}", part);
+        };
+        assert_eq!(params, Params::new(NameValuePairs::new()));
+    }
+
+    #[test]
+    fn filter_requires_data() {
+        let mut stream = Stream::<server::State>::default();
+
+        stream.parse(begin_request(Role::Filter)).unwrap();
+        stream
+            .parse(frame(Standard::Params, b"\x01\x01ab"))
+            .unwrap();
+        stream.parse(frame(Standard::Params, b"")).unwrap();
+        stream.parse(frame(Standard::Stdin, b"")).unwrap();
+
+        let result = stream.parse(frame(Standard::Data, b""));
+        assert!(matches!(
+            result,
+            Err(server::ParseRequestError::DataIsRequiredForFilterApplications)
+        ));
+    }
+
+    #[test]
+    fn management_frame_is_rejected() {
+        let frame = Frame::new(0, Standard::Stdout.into(), BytesMut::from(&b"out"[..]));
+        let mut stream = Stream::<client::State>::default();
+
+        assert!(matches!(
+            stream.parse(frame),
+            Err(client::ParseResponseError::UnexpectedManagementRecord)
+        ));
+
+        let frame = Frame::new(0, Standard::BeginRequest.into(), BytesMut::new());
+        let mut stream = Stream::<server::State>::default();
+
+        assert!(matches!(
+            stream.parse(frame),
+            Err(server::ParseRequestError::UnexpectedManagementRecord)
+        ));
+    }
+
+    #[test]
+    fn padded_empty_frame_ends_stdout() {
+        let mut codec = FastCgiCodec::new();
+        let mut src = BytesMut::from(
+            &[
+                1, 6, 0, 1, 0, 3, 5, 0, b'o', b'u', b't', 0, 0, 0, 0, 0, // Stdout
+                1, 6, 0, 1, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, // Padded EndOfStream
+            ][..],
+        );
+
+        let mut stream = Stream::<client::State>::default();
+
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        assert!(stream.parse(frame).unwrap().is_none());
+
+        let frame = codec.decode(&mut src).unwrap().unwrap();
+        let part = stream.parse(frame).unwrap();
+        let Some(response::Part::Stdout(Some(stdout))) = part else {
+            panic!("expected Stdout, got {:?}", part);
+        };
+        assert_eq!(&stdout[..], b"out");
+    }
+
+    #[test]
+    fn end_request_without_stdout() {
+        let mut stream = Stream::<client::State>::default();
+
+        let part = stream
+            .parse(end_request(ProtocolStatus::CantMpxConn))
+            .unwrap();
+
+        let Some(response::Part::EndRequest(end_request)) = part else {
+            panic!("expected an EndRequest, got {:?}", part);
+        };
+
+        assert_eq!(
+            end_request.get_protocol_status(),
+            ProtocolStatus::CantMpxConn
+        );
+    }
+
+    #[test]
+    fn required_end_of_stderr() {
+        let config = Config::new().require_end_of_stderr(true);
+
+        let mut stream = Stream::with_state(client::State::<Defrag>::from_config(&config));
+        stream.parse(frame(Standard::Stdout, b"")).unwrap();
+
+        let result = stream.parse(end_request(ProtocolStatus::RequestComplete));
+        assert!(matches!(
+            result,
+            Err(client::ParseResponseError::MissingEndOfStderr)
+        ));
+
+        let mut stream = Stream::with_state(client::State::<Defrag>::from_config(&config));
+        stream.parse(frame(Standard::Stdout, b"")).unwrap();
+
+        let part = stream.parse(frame(Standard::Stderr, b"")).unwrap();
+        assert!(matches!(part, Some(response::Part::Stderr(None))));
+
+        let part = stream
+            .parse(end_request(ProtocolStatus::RequestComplete))
+            .unwrap();
+        assert!(matches!(part, Some(response::Part::EndRequest(_))));
+    }
+
+    #[test]
+    fn stream_size_limits() {
+        let config = Config::new().max_stdout_size(4).max_stderr_size(2);
+
+        let mut stream = Stream::with_state(client::State::<Defrag>::from_config(&config));
+        stream.parse(frame(Standard::Stdout, b"ou")).unwrap();
+        stream.parse(frame(Standard::Stdout, b"t")).unwrap();
+        stream.parse(frame(Standard::Stderr, b"er")).unwrap();
+
+        let result = stream.parse(frame(Standard::Stderr, b"r"));
+        assert!(matches!(
+            result,
+            Err(client::ParseResponseError::ExceededMaximumStderrSize(_))
+        ));
+
+        let mut stream = Stream::with_state(client::State::<Defrag>::from_config(&config));
+        stream.parse(frame(Standard::Stdout, b"out")).unwrap();
+
+        let result = stream.parse(frame(Standard::Stdout, b"out"));
+        assert!(matches!(
+            result,
+            Err(client::ParseResponseError::ExceededMaximumStdoutSize(_))
+        ));
+    }
+
+    #[test]
+    fn max_stream_size_applies_to_the_defragmenters() {
+        let config = Config::new().max_stream_size(4);
+
+        let mut stream = Stream::with_state(client::State::<Defrag>::from_config(&config));
+        stream.parse(frame(Standard::Stdout, b"out")).unwrap();
+
+        let result = stream.parse(frame(Standard::Stdout, b"out"));
+        assert!(matches!(
+            result,
+            Err(client::ParseResponseError::ExceededMaximumStreamSize(_))
+        ));
+
+        let mut stream = Stream::with_state(server::State::<Defrag>::from_config(&config));
+        stream.parse(begin_request(Role::Responder)).unwrap();
+        stream
+            .parse(frame(Standard::Params, b"\x01\x01ab"))
+            .unwrap();
+
+        let result = stream.parse(frame(Standard::Params, b"\x01\x01ab"));
+        assert!(matches!(
+            result,
+            Err(server::ParseRequestError::ExceededMaximumStreamSize(_))
+        ));
+    }
+
+    #[test]
+    fn max_stream_size_applies_to_streamed_params() {
+        let config = Config::new().max_stream_size(4);
+        let state = server::State::<Defrag>::from_config(&config).with_params_streaming();
+
+        let mut stream = Stream::with_state(state);
+        stream.parse(begin_request(Role::Responder)).unwrap();
+        stream
+            .parse(frame(Standard::Params, b"\x01\x01ab"))
+            .unwrap();
+
+        // The length prefix of an incomplete pair is counted too, so it can't be buffered
+        // indefinitely.
+        let result = stream.parse(frame(Standard::Params, b"\x7f"));
+        assert!(matches!(
+            result,
+            Err(server::ParseRequestError::ExceededMaximumStreamSize(_))
+        ));
+    }
+
+    #[test]
+    fn end_request_with_unterminated_streams() {
+        let mut stream = Stream::<client::State>::default();
+
+        stream.parse(frame(Standard::Stdout, b"out")).unwrap();
+        stream.parse(frame(Standard::Stderr, b"err")).unwrap();
+
+        let part = stream
+            .parse(end_request(ProtocolStatus::RequestComplete))
+            .unwrap();
+
+        let Some(response::Part::PartialEndRequest((Some(stdout), Some(stderr), _))) = part else {
+            panic!("expected a PartialEndRequest, got {:?}", part);
+        };
+
+        assert_eq!(&stdout[..], b"out");
+        assert_eq!(&stderr[..], b"err");
+    }
+
+    #[test]
+    fn stdout_after_ended_stderr() {
+        let mut stream = Stream::<client::State>::default();
+
+        assert!(stream
+            .parse(frame(Standard::Stderr, b"err"))
+            .unwrap()
+            .is_none());
+
+        let part = stream.parse(frame(Standard::Stderr, b"")).unwrap();
+        let Some(response::Part::Stderr(Some(stderr))) = part else {
+            panic!("expected Stderr, got {:?}", part);
+        };
+        assert_eq!(&stderr[..], b"err");
+
+        assert!(stream
+            .parse(frame(Standard::Stdout, b"out"))
+            .unwrap()
+            .is_none());
+
+        let part = stream.parse(frame(Standard::Stdout, b"")).unwrap();
+        let Some(response::Part::Stdout(Some(stdout))) = part else {
+            panic!("expected Stdout, got {:?}", part);
+        };
+        assert_eq!(&stdout[..], b"out");
+
+        let part = stream
+            .parse(end_request(ProtocolStatus::RequestComplete))
+            .unwrap();
+        assert!(matches!(part, Some(response::Part::EndRequest(_))));
+    }
+}
@@ -1,9 +1,11 @@
-use crate::codec::Frame;
+use crate::{codec::Frame, record::Id};
 
 use super::state::State;
 
 #[derive(Debug, Default)]
 pub(crate) struct Stream<S: State> {
+    // Set by the first frame of the stream.
+    id: Option<Id>,
     state: S,
 }
 
@@ -11,13 +13,21 @@ impl<S: State> Stream<S>
 where
     S: State,
 {
-    pub(crate) fn new() -> Self {
-        Stream {
-            state: S::default(),
-        }
+    pub(crate) fn with_state(state: S) -> Self {
+        Stream { id: None, state }
+    }
+
+    pub(crate) fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub(crate) fn id(&self) -> Option<Id> {
+        self.id
     }
 
     pub(crate) fn parse(&mut self, frame: Frame) -> Result<Option<S::Output>, S::Error> {
+        self.id.get_or_insert(frame.id);
+
         let transition = S::parse_transition(frame)?;
 
         S::parse_frame(&mut self.state, transition)
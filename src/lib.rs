@@ -1,3 +1,5 @@
+#[cfg(feature = "capture")]
+pub mod capture;
 pub mod client;
 pub mod codec;
 pub mod conn;
@@ -8,6 +10,9 @@ pub mod request;
 pub mod response;
 pub mod server;
 
+#[cfg(feature = "tls")]
+pub use tokio_rustls::rustls;
+
 use conn::{
     connection::{ConnectionRecvError, ConnectionSendError},
     ParseRequestError, ParseResponseError,
@@ -21,6 +26,8 @@ pub const MANAGEMENT_ID: u16 = 0;
 pub enum FastcgiClientError {
     Send(ConnectionSendError),
     Recv(ConnectionRecvError<ParseResponseError>),
+    /// The response wasn't received before the deadline of the request.
+    DeadlineExceeded,
 }
 
 #[derive(Debug)]
@@ -1,21 +1,15 @@
 #[macro_export]
 macro_rules! await_variant {
     ($connection:ident, Part::$variant:ident) => {{
-        loop {
-            if let Some(result) = $connection.poll_frame().await {
-                match result? {
-                    Part::$variant(inner) => {
-                        break inner;
-                    }
-                    Part::AbortRequest => {
-                        // TODO: Handle aborted request on the connection.
-                        $connection.close_stream();
+        match $connection.recv_part().await? {
+            Part::$variant(inner) => inner,
+            Part::AbortRequest => {
+                // TODO: Handle aborted request on the connection.
+                $connection.close_stream();
 
-                        return Ok(None);
-                    }
-                    _ => unreachable!(),
-                }
+                return Ok(None);
             }
+            _ => unreachable!(),
         }
     }};
 }
@@ -69,13 +63,14 @@ macro_rules! impl_std_meta {
     // Doesn't support module paths nor 'where' constraints.
     (
         $(
-            ($variant:ident $(< $( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+ >)?, $rkind:ident, $dkind:ident);
+            ($variant:ident $(< $( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+ >)?, $sent_by:ident, $rkind:ident, $dkind:ident);
         )+
     ) => {
         $(
             impl $(< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? Meta for $variant $(< $( $lt ),+ >)?
             {
                 const TYPE: RecordType = RecordType::Standard(Standard::$variant);
+                type SentBy = $sent_by;
                 type RecordKind = $rkind;
                 type DataKind = $dkind;
             }
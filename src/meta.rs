@@ -42,6 +42,7 @@ mod private {
 
 pub trait Meta: private::Sealed {
     const TYPE: RecordType;
+    type SentBy: SentBy;
     type RecordKind: RecordKind;
     type DataKind: DataKind;
 }
@@ -95,6 +96,7 @@ pub trait MetaExt {
 // Implement `Meta` for extended record types.
 impl<T: MetaExt> Meta for T {
     const TYPE: RecordType = RecordType::Custom(T::TYPE);
+    type SentBy = T::SentBy;
     type RecordKind = Management;
     type DataKind = T::DataKind;
 }
@@ -0,0 +1,202 @@
+//! `Arbitrary` implementations of records, used for property-based testing.
+
+use bytes::Bytes;
+use proptest::{collection::vec, prelude::*};
+
+use super::{
+    BeginRequest, EndRequest, GetValues, NameValuePair, NameValuePairs, Params, ProtocolStatus,
+    Role,
+};
+
+/// Generates non-empty params, biased towards the lengths at which the length encoding switches
+/// from 1 to 4 bytes.
+fn param() -> impl Strategy<Value = Bytes> {
+    prop_oneof![vec(any::<u8>(), 1..=256), vec(any::<u8>(), 127..=128)].prop_map(Bytes::from)
+}
+
+fn name_value_pairs(
+    value: impl Strategy<Value = Option<Bytes>>,
+) -> impl Strategy<Value = NameValuePairs> {
+    vec((param(), value), 1..32).prop_map(|pairs| {
+        pairs
+            .into_iter()
+            .fold(NameValuePairs::new(), |nvps, (name, value)| {
+                nvps.insert_nvp(NameValuePair::new_unchecked(name, value))
+            })
+    })
+}
+
+impl Arbitrary for Role {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(Role::Responder),
+            Just(Role::Authorizer),
+            Just(Role::Filter)
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for BeginRequest {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<Role>(), any::<bool>())
+            .prop_map(|(role, keep_conn)| BeginRequest::from_parts(role, keep_conn))
+            .boxed()
+    }
+}
+
+impl Arbitrary for ProtocolStatus {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(ProtocolStatus::RequestComplete),
+            Just(ProtocolStatus::CantMpxConn),
+            Just(ProtocolStatus::Overloaded),
+            Just(ProtocolStatus::UnknownRole)
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for EndRequest {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<u32>(), any::<ProtocolStatus>())
+            .prop_map(|(app_status, protocol_status)| EndRequest::new(app_status, protocol_status))
+            .boxed()
+    }
+}
+
+impl Arbitrary for NameValuePair {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (param(), proptest::option::of(param()))
+            .prop_map(|(name, value)| NameValuePair::new_unchecked(name, value))
+            .boxed()
+    }
+}
+
+impl Arbitrary for Params {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        name_value_pairs(param().prop_map(Some))
+            .prop_map(Params::new)
+            .boxed()
+    }
+}
+
+impl Arbitrary for GetValues {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        name_value_pairs(Just(None)).prop_map(GetValues).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{BufMut, BytesMut};
+
+    use crate::{
+        codec::{decode_frame, RingBuffer},
+        record::{
+            DecodeFrame, EncodeChunk, EncodeFrame, Header, Standard, DEFAULT_MAX_PAYLOAD_SIZE,
+        },
+    };
+
+    use super::*;
+
+    fn encode_frame<T: EncodeFrame>(record: T) -> BytesMut {
+        let mut buffer = RingBuffer::with_capacity(DEFAULT_MAX_PAYLOAD_SIZE + 1);
+        record.encode_frame(&mut buffer.write_only()).unwrap();
+
+        let mut dst = BytesMut::new();
+        dst.put(&mut buffer);
+        dst
+    }
+
+    fn encode_stream<T: EncodeChunk>(mut record: T) -> BytesMut {
+        let mut buffer = RingBuffer::with_capacity(DEFAULT_MAX_PAYLOAD_SIZE + 1);
+        let mut dst = BytesMut::new();
+
+        while let Some(result) = record.encode_chunk(&mut buffer.write_only()) {
+            result.unwrap();
+            dst.put(&mut buffer);
+        }
+
+        dst
+    }
+
+    proptest! {
+        #[test]
+        fn begin_request_round_trip(begin_request in any::<BeginRequest>()) {
+            let src = encode_frame(begin_request);
+
+            prop_assert_eq!(BeginRequest::decode_frame(src).unwrap(), begin_request);
+        }
+
+        #[test]
+        fn end_request_round_trip(end_request in any::<EndRequest>()) {
+            let src = encode_frame(end_request);
+
+            prop_assert_eq!(EndRequest::decode_frame(src).unwrap(), end_request);
+        }
+
+        #[test]
+        fn name_value_pair_round_trip(nvp in any::<NameValuePair>()) {
+            let mut src = BytesMut::new();
+            NameValuePairs::new()
+                .insert_nvp(nvp.clone())
+                .encode_chunk(&mut src)
+                .unwrap()
+                .unwrap();
+
+            let nvps = NameValuePairs::decode(src, |_| true).unwrap();
+
+            prop_assert_eq!(nvps.as_ref(), &vec![nvp]);
+        }
+
+        #[test]
+        fn params_round_trip(params in any::<Params>()) {
+            let src = encode_stream(params.clone());
+
+            prop_assert_eq!(Params::decode_frame(src).unwrap(), params);
+        }
+
+        #[test]
+        fn get_values_round_trip(get_values in any::<GetValues>()) {
+            let src = encode_frame(get_values.clone());
+
+            prop_assert_eq!(GetValues::decode_frame(src).unwrap(), get_values);
+        }
+
+        #[test]
+        fn padded_frame_round_trip(payload in vec(any::<u8>(), 0..1024), padding in any::<u8>()) {
+            let mut src = BytesMut::new();
+            Header::encode(Standard::Stdout.into(), 1, payload.len() as u16, padding, &mut src);
+            src.put(&payload[..]);
+            src.put_bytes(0, padding as usize);
+
+            let (id, record_type, decoded) = decode_frame(&src).unwrap().unwrap();
+
+            prop_assert_eq!(id, 1);
+            prop_assert_eq!(record_type, Standard::Stdout);
+            prop_assert_eq!(&decoded[..], &payload[..]);
+        }
+    }
+}
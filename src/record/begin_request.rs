@@ -77,11 +77,13 @@ impl BeginRequest {
             return Err(DecodeFrameError::InsufficientDataInBuffer);
         }
 
-        let role = u16::from_be_bytes(src[..2].try_into().unwrap()).try_into()?;
+        let role = u16::from_be_bytes(src[..2].try_into().unwrap())
+            .try_into()
+            .map_err(|_| DecodeFrameError::corrupted(&src))?;
 
         // Check if the last 5 bytes are all 0.
         if (u64::from_be_bytes(src[..].try_into().unwrap()) << (3 * 8)) > 0 {
-            return Err(DecodeFrameError::CorruptedFrame);
+            return Err(DecodeFrameError::corrupted(&src));
         }
 
         let begin_request = BeginRequest::new(role);
@@ -118,6 +120,7 @@ impl DecodeFrame for BeginRequest {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -133,4 +136,21 @@ mod tests {
 
         assert_eq!(begin_request, result);
     }
+
+    #[test]
+    fn decode_unknown_role() {
+        let src = BytesMut::from(&[0, 9, 0, 0, 0, 0, 0, 0][..]);
+
+        let err = BeginRequest::decode(src).unwrap_err();
+        assert!(err.is_corrupted());
+
+        #[cfg(feature = "diagnostics")]
+        assert_eq!(
+            format!("{:?}", err),
+            "CorruptedPayload(00 09 00 00 00 00 00 00)"
+        );
+
+        #[cfg(not(feature = "diagnostics"))]
+        assert_eq!(format!("{:?}", err), "CorruptedPayload(.. (8 bytes))");
+    }
 }
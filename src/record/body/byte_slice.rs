@@ -36,6 +36,10 @@ impl ByteSlice {
         })
     }
 
+    /// Encodes the next chunk of the bytes into the buffer.
+    ///
+    /// The chunk is split off without copying, so the bytes are only copied once, when they're
+    /// written into the buffer of the codec.
     pub fn encode_chunk<B: BufMut>(&mut self, buf: &mut B) -> Option<Result<(), EncodeFrameError>> {
         if self.bytes.is_empty() {
             return None;
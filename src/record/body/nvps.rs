@@ -2,6 +2,11 @@ use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 use crate::record::{DecodeFrameError, EncodeFrameError};
 
+/// An ordered list of name-value pairs.
+///
+/// Pairs are encoded in insertion order, and decoded in the order they were received. Duplicate
+/// names are kept, so applications which read the first occurrence of a name behave
+/// deterministically.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct NameValuePairs {
     inner: Vec<NameValuePair>,
@@ -33,9 +38,12 @@ impl NameValuePairs {
             return None;
         }
 
-        // Find the position at which the buffer can no longer fit another nvp.
+        // Find the position at which the buffer can no longer fit another nvp, unless all of
+        // them fit, which is the common case for params.
         let mut size = 0;
-        let drain = match self.inner.iter().position(|nvp| {
+        let drain = if self.size_hint() <= buf.remaining_mut() {
+            self.inner.drain(..)
+        } else if let Some(index) = self.inner.iter().position(|nvp| {
             let hint = nvp.size_hint();
 
             if size + hint <= buf.remaining_mut() {
@@ -45,8 +53,9 @@ impl NameValuePairs {
                 true
             }
         }) {
-            Some(index) => self.inner.drain(..index),
-            None => self.inner.drain(..),
+            self.inner.drain(..index)
+        } else {
+            self.inner.drain(..)
         };
 
         for nvp in drain {
@@ -65,7 +74,13 @@ impl NameValuePairs {
         let mut nvps = NameValuePairs::new();
 
         while src.has_remaining() {
-            let nvp = NameValuePair::decode(&mut src)?;
+            let nvp = NameValuePair::decode(&mut src).map_err(|e| match e {
+                // The source holds the complete stream, so no more data will follow.
+                DecodeFrameError::InsufficientDataInBuffer => {
+                    DecodeFrameError::TruncatedNameValuePair
+                }
+                e => e,
+            })?;
 
             if !validate(&nvp) {
                 // TODO: Let users define errors.
@@ -79,6 +94,15 @@ impl NameValuePairs {
     }
 }
 
+impl FromIterator<NameValuePair> for NameValuePairs {
+    /// Collects the pairs in iteration order.
+    fn from_iter<I: IntoIterator<Item = NameValuePair>>(iter: I) -> Self {
+        Self {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
 impl IntoIterator for NameValuePairs {
     type Item = NameValuePair;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -100,6 +124,49 @@ impl AsMut<Vec<NameValuePair>> for NameValuePairs {
     }
 }
 
+/// Decodes name-value pairs incrementally, as the payloads of a stream arrive.
+///
+/// A pair may be split across payloads, in which case it's only returned once the rest of it
+/// has been inserted.
+#[derive(Debug, Default)]
+pub struct NameValuePairDecoder {
+    buffer: BytesMut,
+}
+
+impl NameValuePairDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert_payload(&mut self, payload: BytesMut) {
+        self.buffer.unsplit(payload);
+    }
+
+    /// Returns the next complete pair, or None if more data is required.
+    pub fn decode_next(&mut self) -> Result<Option<NameValuePair>, DecodeFrameError> {
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        match NameValuePair::decode(&mut self.buffer) {
+            Ok(nvp) => Ok(Some(nvp)),
+            Err(DecodeFrameError::InsufficientDataInBuffer) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Ends the stream, returning an error if it ended in the middle of a pair.
+    pub fn finish(&mut self) -> Result<(), DecodeFrameError> {
+        if self.buffer.is_empty() {
+            Ok(())
+        } else {
+            self.buffer.clear();
+
+            Err(DecodeFrameError::TruncatedNameValuePair)
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Param {
     Short(Bytes),
@@ -189,6 +256,8 @@ impl From<Bytes> for Param {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NameValuePair {
     pub name: Param,
+    /// FastCGI encodes a missing and an empty value identically, as a length of 0. The
+    /// canonical representation of both is `None`, which is also what decoding returns.
     pub value: Option<Param>,
 }
 
@@ -214,7 +283,12 @@ impl NameValuePair {
         Some(Self::new_unchecked(name, None::<&[u8]>))
     }
 
+    /// An empty value is stored as `None`, see [`NameValuePair::value`].
     pub fn new_unchecked(name: impl Into<Bytes>, value: Option<impl Into<Bytes>>) -> Self {
+        let value = value
+            .map(Into::into)
+            .filter(|value: &Bytes| !value.is_empty());
+
         Self {
             name: Param::new(name),
             value: value.map(Param::new),
@@ -253,23 +327,32 @@ impl NameValuePair {
         Ok(())
     }
 
+    /// Decodes a single name-value pair.
+    ///
+    /// Returns `InsufficientDataInBuffer` if the source ends before the pair is complete, in
+    /// which case the source is left untouched, and a corrupted error if the pair itself is
+    /// invalid.
     fn decode(src: &mut BytesMut) -> Result<Self, DecodeFrameError> {
-        let Some(name_len) = Param::decode_length(src) else {
-            return Err(DecodeFrameError::CorruptedFrame);
+        let mut lengths = &src[..];
+
+        let Some(name_len) = Param::decode_length(&mut lengths) else {
+            return Err(DecodeFrameError::InsufficientDataInBuffer);
         };
 
         if name_len == 0 {
-            return Err(DecodeFrameError::CorruptedFrame);
+            return Err(DecodeFrameError::corrupted(src));
         }
 
-        let Some(value_len) = Param::decode_length(src) else {
-            return Err(DecodeFrameError::CorruptedFrame);
+        let Some(value_len) = Param::decode_length(&mut lengths) else {
+            return Err(DecodeFrameError::InsufficientDataInBuffer);
         };
 
-        if src.remaining() < name_len + value_len {
-            return Err(DecodeFrameError::CorruptedFrame);
+        if lengths.remaining() < name_len + value_len {
+            return Err(DecodeFrameError::InsufficientDataInBuffer);
         }
 
+        src.advance(src.len() - lengths.len());
+
         let name = src.split_to(name_len).freeze();
         let value = (value_len > 0).then(|| src.split_to(value_len).freeze());
 
@@ -280,6 +363,7 @@ impl NameValuePair {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -309,6 +393,73 @@ mod tests {
         assert_eq!(nvp, res);
     }
 
+    #[test]
+    fn empty_value_is_canonicalized() {
+        let nvp = NameValuePair::new_unchecked("a", Some(""));
+        assert_eq!(nvp, NameValuePair::new_empty("a").unwrap());
+
+        let mut buffer = BytesMut::new();
+        nvp.clone().encode(&mut buffer).unwrap();
+
+        assert_eq!(&buffer[..], &[1, 0, b'a']);
+        assert_eq!(NameValuePair::decode(&mut buffer).unwrap(), nvp);
+    }
+
+    #[test]
+    fn pairs_keep_insertion_order() {
+        let pairs = [("b", "1"), ("a", "2"), ("b", "3"), ("c", "4")];
+
+        let mut nvps = pairs
+            .into_iter()
+            .map(|(name, value)| NameValuePair::new(name, value).unwrap())
+            .collect::<NameValuePairs>();
+        let expected = nvps.clone();
+
+        let mut buffer = BytesMut::new();
+        nvps.encode_chunk(&mut buffer).unwrap().unwrap();
+
+        assert_eq!(&buffer[..], b"\x01\x01b1\x01\x01a2\x01\x01b3\x01\x01c4");
+        assert_eq!(NameValuePairs::decode(buffer, |_| true), Ok(expected));
+    }
+
+    #[test]
+    fn small_pairs_fit_in_one_chunk() {
+        let mut nvps = (0..15)
+            .map(|i| NameValuePair::new(format!("NAME_{i}"), format!("value_{i}")).unwrap())
+            .collect::<NameValuePairs>();
+        let size = nvps.size_hint();
+
+        let mut buffer = BytesMut::new();
+        nvps.encode_chunk(&mut (&mut buffer).limit(u16::MAX as usize))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(buffer.len(), size);
+        assert!(nvps.encode_chunk(&mut buffer).is_none());
+    }
+
+    #[test]
+    fn large_pairs_are_split_across_chunks() {
+        let mut nvps = (0..4)
+            .map(|i| NameValuePair::new(format!("NAME_{i}"), vec![b'v'; 20_000]).unwrap())
+            .collect::<NameValuePairs>();
+        let size = nvps.size_hint();
+
+        let mut first = BytesMut::new();
+        nvps.encode_chunk(&mut (&mut first).limit(u16::MAX as usize))
+            .unwrap()
+            .unwrap();
+
+        let mut second = BytesMut::new();
+        nvps.encode_chunk(&mut (&mut second).limit(u16::MAX as usize))
+            .unwrap()
+            .unwrap();
+
+        assert!(first.len() <= u16::MAX as usize);
+        assert_eq!(first.len() + second.len(), size);
+        assert!(nvps.encode_chunk(&mut first).is_none());
+    }
+
     #[test]
     fn length_encoding_decoding() {
         let length = 255;
@@ -322,4 +473,89 @@ mod tests {
         assert!(buffer.is_empty());
         assert_eq!(length, length_2);
     }
+
+    #[test]
+    fn length_with_high_bit_and_insufficient_data() {
+        let mut buffer = BytesMut::from(&[0x80][..]);
+
+        assert!(Param::decode_length(&mut buffer).is_none());
+        assert_eq!(buffer.len(), 1);
+    }
+
+    #[test]
+    fn decode_incomplete_pair() {
+        let nvp = NameValuePair::new_unchecked("a".repeat(200), Some("b"));
+
+        let mut buffer = BytesMut::new();
+        nvp.encode(&mut buffer).unwrap();
+
+        for n in [0, 2, 5, buffer.len() - 1] {
+            let mut src = BytesMut::from(&buffer[..n]);
+
+            assert_eq!(
+                NameValuePair::decode(&mut src),
+                Err(DecodeFrameError::InsufficientDataInBuffer)
+            );
+        }
+    }
+
+    #[test]
+    fn decode_truncated_stream() {
+        let nvp = NameValuePair::new_unchecked("a", Some("b"));
+
+        let mut buffer = BytesMut::new();
+        nvp.encode(&mut buffer).unwrap();
+        buffer.truncate(buffer.len() - 1);
+
+        assert_eq!(
+            NameValuePairs::decode(buffer, |_| true),
+            Err(DecodeFrameError::TruncatedNameValuePair)
+        );
+    }
+
+    #[test]
+    fn decode_empty_name() {
+        let mut src = BytesMut::from(&[0, 1, b'b'][..]);
+
+        assert!(NameValuePair::decode(&mut src).unwrap_err().is_corrupted());
+    }
+
+    #[test]
+    fn decoder_with_pair_split_across_payloads() {
+        let first = NameValuePair::new_unchecked("a".repeat(200), Some("b"));
+        let second = NameValuePair::new_unchecked("c", Some("d"));
+
+        let mut buffer = BytesMut::new();
+        first.clone().encode(&mut buffer).unwrap();
+        second.clone().encode(&mut buffer).unwrap();
+
+        let mut decoder = NameValuePairDecoder::new();
+
+        // Split inside the 4-byte length of the first pair's name.
+        decoder.insert_payload(buffer.split_to(3));
+        assert_eq!(decoder.decode_next(), Ok(None));
+
+        decoder.insert_payload(buffer.split_to(204));
+        assert_eq!(decoder.decode_next(), Ok(Some(first)));
+        assert_eq!(decoder.decode_next(), Ok(None));
+
+        decoder.insert_payload(buffer.split_to(buffer.len() - 1));
+        assert_eq!(decoder.decode_next(), Ok(None));
+
+        decoder.insert_payload(buffer);
+        assert_eq!(decoder.decode_next(), Ok(Some(second)));
+        assert_eq!(decoder.finish(), Ok(()));
+    }
+
+    #[test]
+    fn decoder_finished_mid_pair() {
+        let mut decoder = NameValuePairDecoder::new();
+        decoder.insert_payload(BytesMut::from(&[1, 1, b'a'][..]));
+
+        assert_eq!(decoder.decode_next(), Ok(None));
+        assert_eq!(
+            decoder.finish(),
+            Err(DecodeFrameError::TruncatedNameValuePair)
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use bytes::BufMut;
+
 use crate::{
     codec::Buffer,
     record::{EncodeChunk, EncodeFrameError},
@@ -5,13 +7,17 @@ use crate::{
 
 pub struct StreamChunker<T: EncodeChunk> {
     inner: Option<T>,
+    encoded_len: u64,
 }
 
 impl<T: EncodeChunk> StreamChunker<T> {
     pub fn encode(&mut self, buf: &mut Buffer) -> Option<Result<(), EncodeFrameError>> {
         if let Some(stream) = self.inner.as_mut() {
+            let remaining = buf.remaining_mut();
             let result = stream.encode_chunk(buf);
 
+            self.encoded_len += (remaining - buf.remaining_mut()) as u64;
+
             if result.is_none() {
                 self.inner.take();
             }
@@ -25,6 +31,11 @@ impl<T: EncodeChunk> StreamChunker<T> {
     pub fn is_empty(&self) -> bool {
         self.inner.is_none()
     }
+
+    /// Returns the number of bytes of the stream which were encoded so far.
+    pub fn encoded_len(&self) -> u64 {
+        self.encoded_len
+    }
 }
 
 pub(crate) trait IntoStreamChunker {
@@ -37,6 +48,9 @@ impl<T: EncodeChunk> IntoStreamChunker for T {
     type Item = T;
 
     fn into_stream_chunker(self) -> StreamChunker<Self::Item> {
-        StreamChunker { inner: Some(self) }
+        StreamChunker {
+            inner: Some(self),
+            encoded_len: 0,
+        }
     }
 }
@@ -64,6 +64,18 @@ impl From<Bytes> for Data {
     }
 }
 
+impl From<Vec<u8>> for Data {
+    fn from(value: Vec<u8>) -> Self {
+        Self::new_bytes(Bytes::from(value))
+    }
+}
+
+impl From<String> for Data {
+    fn from(value: String) -> Self {
+        Self::new_bytes(Bytes::from(value))
+    }
+}
+
 impl TryFrom<File> for Data {
     type Error = std::io::Error;
 
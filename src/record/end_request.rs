@@ -1,3 +1,5 @@
+use std::fmt;
+
 use bytes::{Buf, BufMut, BytesMut};
 
 use crate::codec::Buffer;
@@ -24,6 +26,28 @@ impl From<u8> for ProtocolStatus {
     }
 }
 
+impl ProtocolStatus {
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the name of the status in the FastCGI specification.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RequestComplete => "FCGI_REQUEST_COMPLETE",
+            Self::CantMpxConn => "FCGI_CANT_MPX_CONN",
+            Self::Overloaded => "FCGI_OVERLOADED",
+            Self::UnknownRole => "FCGI_UNKNOWN_ROLE",
+        }
+    }
+}
+
+impl fmt::Display for ProtocolStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct EndRequest {
     app_status: u32,
@@ -44,7 +68,7 @@ impl EndRequest {
         }
 
         dst.put_u32(self.app_status);
-        dst.put_u8(self.protocol_status as u8);
+        dst.put_u8(self.protocol_status.as_u8());
         dst.put_bytes(0, 3);
 
         Ok(())
@@ -52,12 +76,12 @@ impl EndRequest {
 
     pub fn decode(mut src: BytesMut) -> Result<EndRequest, DecodeFrameError> {
         if src.len() != 8 {
-            return Err(DecodeFrameError::CorruptedFrame);
+            return Err(DecodeFrameError::corrupted(&src));
         }
 
         // Check that the last 3 bytes are all 0.
         if (u64::from_be_bytes(src[..].try_into().unwrap()) << (5 * 8)) > 0 {
-            return Err(DecodeFrameError::CorruptedFrame);
+            return Err(DecodeFrameError::corrupted(&src));
         };
 
         let app_status = src.get_u32();
@@ -87,6 +111,7 @@ impl DecodeFrame for EndRequest {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -102,4 +127,28 @@ mod tests {
 
         assert_eq!(end_request, result);
     }
+
+    #[test]
+    fn protocol_status_round_trip() {
+        for status in [
+            ProtocolStatus::RequestComplete,
+            ProtocolStatus::CantMpxConn,
+            ProtocolStatus::Overloaded,
+            ProtocolStatus::UnknownRole,
+        ] {
+            assert_eq!(ProtocolStatus::from(status.as_u8()), status);
+        }
+    }
+
+    #[test]
+    fn protocol_status_display() {
+        assert_eq!(
+            ProtocolStatus::RequestComplete.to_string(),
+            "FCGI_REQUEST_COMPLETE"
+        );
+        assert_eq!(
+            ProtocolStatus::CantMpxConn.to_string(),
+            "FCGI_CANT_MPX_CONN"
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use bytes::BytesMut;
 
 use crate::codec::Buffer;
@@ -40,6 +42,18 @@ impl GetValuesResult {
     pub fn validate(nvp: &NameValuePair) -> bool {
         !nvp.name.inner().is_empty() && nvp.value.is_some()
     }
+
+    /// Iterates over the names and values, lossily decoded as UTF-8 for displaying or logging.
+    pub fn iter_str(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+        self.0.as_ref().iter().map(|nvp| {
+            let value = nvp.value.as_ref().map_or(&[][..], |value| value.inner());
+
+            (
+                String::from_utf8_lossy(nvp.name.inner()),
+                String::from_utf8_lossy(value),
+            )
+        })
+    }
 }
 
 impl EncodeFrame for GetValuesResult {
@@ -58,3 +72,27 @@ impl DecodeFrame for GetValuesResult {
         )?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iter_str() {
+        let result = GetValuesResult(
+            NameValuePairs::new()
+                .insert_nvp(NameValuePair::new("FCGI_MAX_CONNS", "10").unwrap())
+                .insert_nvp(NameValuePair::new("FCGI_MPXS_CONNS", &b"\xff"[..]).unwrap()),
+        );
+
+        let pairs = result.iter_str().collect::<Vec<_>>();
+
+        assert_eq!(
+            pairs,
+            [
+                ("FCGI_MAX_CONNS".into(), "10".into()),
+                ("FCGI_MPXS_CONNS".into(), "\u{fffd}".into()),
+            ]
+        );
+    }
+}
@@ -23,8 +23,8 @@ pub struct Header {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Padding {
+    /// Pads the payload to a multiple of 8 bytes, as recommended by the spec.
     Automatic,
-    Adaptive(fn(u16) -> u8),
     Static(u8),
 }
 
@@ -37,29 +37,6 @@ impl Header {
         }
     }
 
-    pub fn with_padding(mut self, padding: Padding) -> Self {
-        self.padding = Some(padding);
-        self
-    }
-
-    /// Apply padding to this records's payload based on the length of the payload.
-    pub fn with_adaptive_padding(mut self, f: fn(u16) -> u8) -> Self {
-        self.padding = Some(Padding::Adaptive(f));
-        self
-    }
-
-    /// Apply a static amount padding to this records's payload.
-    pub fn with_static_padding(mut self, n: u8) -> Self {
-        self.padding = Some(Padding::Static(n));
-        self
-    }
-
-    /// Avoid adding padding to the records's payload.
-    pub fn without_padding(mut self) -> Self {
-        self.padding = None;
-        self
-    }
-
     pub fn encode<B: BufMut>(
         record_type: RecordType,
         id: u16,
@@ -76,7 +53,12 @@ impl Header {
     }
 
     /// Returns a triple containing the header, content_length, and padding length.
-    pub fn decode(src: &mut BytesMut) -> Result<Option<(Header, u16, u8)>, DecodeCodecError> {
+    ///
+    /// A nonzero reserved byte is rejected, unless `ignore_reserved` is set.
+    pub fn decode(
+        src: &mut BytesMut,
+        ignore_reserved: bool,
+    ) -> Result<Option<(Header, u16, u8)>, DecodeCodecError> {
         if src.len() < HEADER_SIZE {
             return Ok(None);
         }
@@ -85,7 +67,7 @@ impl Header {
             return Err(DecodeCodecError::IncompatibleVersion);
         }
 
-        if src[7] != 0 {
+        if src[7] != 0 && !ignore_reserved {
             return Err(DecodeCodecError::CorruptedHeader);
         }
 
@@ -122,7 +104,6 @@ impl Padding {
         match (self, content_length) {
             (Padding::Automatic, 0) => 0,
             (Padding::Automatic, n) => Self::pad_to_multiple_of_8(n),
-            (Padding::Adaptive(f), n) => f(n),
             (Padding::Static(n), _) => n,
         }
     }
@@ -1,5 +1,8 @@
 pub(crate) mod header;
 
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+
 pub mod abort_request;
 pub mod begin_request;
 pub mod body;
@@ -30,7 +33,7 @@ use bytes::BytesMut;
 use crate::{
     codec::Buffer,
     impl_std_meta,
-    meta::{Application, Discrete, Management, Meta, Stream},
+    meta::{Application, Client, Discrete, Management, Meta, Server, Stream},
 };
 
 pub const DEFAULT_MAX_PAYLOAD_SIZE: usize = u16::MAX as usize;
@@ -63,22 +66,6 @@ pub(crate) struct Record<T> {
 }
 
 impl<T> Record<T> {
-    pub fn get_header(&self) -> &Header {
-        &self.header
-    }
-
-    pub fn get_header_mut(&mut self) -> &mut Header {
-        &mut self.header
-    }
-
-    pub fn get_body(&self) -> &T {
-        &self.body
-    }
-
-    pub fn get_body_mut(&mut self) -> &mut T {
-        &mut self.body
-    }
-
     pub fn into_parts(self) -> (Header, T) {
         (self.header, self.body)
     }
@@ -126,17 +113,17 @@ impl<T: Meta> IntoRecord for T {
 }
 
 impl_std_meta! {
-    (BeginRequest, Application, Discrete);
-    (AbortRequest, Application, Discrete);
-    (EndRequest, Application, Discrete);
-    (Params, Application, Stream);
-    (Stdin, Application, Stream);
-    (Stdout, Application, Stream);
-    (Stderr, Application, Stream);
-    (Data, Application, Stream);
-    (GetValues, Management, Discrete);
-    (GetValuesResult, Management, Discrete);
-    (UnknownType, Management, Discrete);
+    (BeginRequest, Client, Application, Discrete);
+    (AbortRequest, Client, Application, Discrete);
+    (EndRequest, Server, Application, Discrete);
+    (Params, Client, Application, Stream);
+    (Stdin, Client, Application, Stream);
+    (Stdout, Server, Application, Stream);
+    (Stderr, Server, Application, Stream);
+    (Data, Client, Application, Stream);
+    (GetValues, Client, Management, Discrete);
+    (GetValuesResult, Server, Management, Discrete);
+    (UnknownType, Server, Management, Discrete);
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -148,5 +135,92 @@ pub enum EncodeFrameError {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DecodeFrameError {
     CorruptedFrame,
+    /// Like `CorruptedFrame`, including the start of the payload which failed to decode. The
+    /// snapshot only holds bytes if the `diagnostics` feature is enabled.
+    CorruptedPayload(PayloadSnapshot),
     InsufficientDataInBuffer,
+    /// A complete params stream ended in the middle of a name-value pair, which points to a
+    /// lost frame rather than to corrupted data.
+    TruncatedNameValuePair,
+}
+
+impl DecodeFrameError {
+    /// Returns the error for a corrupted payload, which includes a snapshot of the payload if
+    /// the `diagnostics` feature is enabled.
+    pub(crate) fn corrupted(payload: &[u8]) -> Self {
+        DecodeFrameError::CorruptedPayload(PayloadSnapshot::new(payload))
+    }
+
+    /// Returns true if the frame is corrupted, regardless of whether a snapshot was taken.
+    pub fn is_corrupted(&self) -> bool {
+        matches!(
+            self,
+            DecodeFrameError::CorruptedFrame | DecodeFrameError::CorruptedPayload(_)
+        )
+    }
+}
+
+/// The first bytes of a payload which failed to decode.
+///
+/// The snapshot is bounded to [`PayloadSnapshot::MAX_LEN`] bytes, so that errors which are logged
+/// don't leak large payloads. Its `Debug` output is hex encoded. Without the `diagnostics`
+/// feature, the snapshot is empty, and only records the length of the payload.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PayloadSnapshot {
+    bytes: [u8; PayloadSnapshot::MAX_LEN],
+    len: usize,
+    payload_len: usize,
+}
+
+impl PayloadSnapshot {
+    pub const MAX_LEN: usize = 32;
+
+    fn new(payload: &[u8]) -> Self {
+        let len = if cfg!(feature = "diagnostics") {
+            payload.len().min(Self::MAX_LEN)
+        } else {
+            0
+        };
+        let mut bytes = [0; Self::MAX_LEN];
+
+        bytes[..len].copy_from_slice(&payload[..len]);
+
+        Self {
+            bytes,
+            len,
+            payload_len: payload.len(),
+        }
+    }
+
+    /// Returns the captured bytes.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    /// Returns the length of the whole payload.
+    pub fn payload_len(&self) -> usize {
+        self.payload_len
+    }
+}
+
+impl std::fmt::Debug for PayloadSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, byte) in self.bytes().iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+
+            write!(f, "{:02x}", byte)?;
+        }
+
+        if self.payload_len > self.len {
+            if self.len > 0 {
+                write!(f, " ")?;
+            }
+
+            write!(f, ".. ({} bytes)", self.payload_len)?;
+        }
+
+        Ok(())
+    }
 }
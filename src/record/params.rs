@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, net::IpAddr, time::SystemTime};
+use std::{marker::PhantomData, net::IpAddr, str::FromStr, time::SystemTime};
 
 use bytes::BytesMut;
 
@@ -17,8 +17,13 @@ pub struct Params {
 }
 
 impl Params {
+    pub(crate) fn new(inner: NameValuePairs) -> Self {
+        Self { inner }
+    }
+
+    /// Params may have an empty value, e.g. the `QUERY_STRING` of a request without a query.
     pub fn validate(nvp: &NameValuePair) -> bool {
-        !nvp.name.inner().is_empty() && nvp.value.is_some()
+        !nvp.name.inner().is_empty()
     }
 
     pub fn insert_nvp(mut self, nvp: NameValuePair) -> Self {
@@ -29,6 +34,60 @@ impl Params {
     pub fn builder<R: RoleTyped>() -> ParamsBuilder<Init, R> {
         ParamsBuilder::new()
     }
+
+    /// Returns the encoded size of the params, excluding record headers and padding.
+    pub fn size_hint(&self) -> usize {
+        self.inner.size_hint()
+    }
+
+    /// Returns the value of the first param with the given name.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.inner
+            .as_ref()
+            .iter()
+            .find(|nvp| nvp.name.inner() == name.as_bytes())
+            .map(|nvp| nvp.value.as_ref().map_or(&[][..], |value| value.inner()))
+    }
+
+    /// Parses the value of the first param with the given name, if it's present.
+    pub fn parse_param<T: FromStr>(
+        &self,
+        name: &'static str,
+    ) -> Result<Option<T>, FromParamsError> {
+        self.get(name)
+            .map(|value| {
+                std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or(FromParamsError::InvalidParam(name))
+            })
+            .transpose()
+    }
+
+    /// Maps the params into a typed struct, see [`FromParams`].
+    pub fn parse<T: FromParams>(&self) -> Result<T, FromParamsError> {
+        T::from_params(self)
+    }
+}
+
+/// Conversion from params into a typed struct.
+///
+/// With the `derive` feature, this can be derived for structs whose fields are annotated with
+/// the name of their param, e.g. `#[param("SERVER_PORT")]`. Fields are parsed with `FromStr`, and
+/// fields of type `Option<T>` may be absent.
+pub trait FromParams: Sized {
+    fn from_params(params: &Params) -> Result<Self, FromParamsError>;
+}
+
+#[cfg(feature = "derive")]
+pub use fastcgi_derive::FromParams;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromParamsError {
+    /// A required param is absent.
+    MissingParam(&'static str),
+    /// A param couldn't be parsed into the type of its field.
+    InvalidParam(&'static str),
 }
 
 impl EncodeChunk for Params {
@@ -93,10 +152,151 @@ impl<S: BuilderState, R: RoleTyped> ParamsBuilder<S, R> {
 
         S::transmute_once(self)
     }
+
+    /// Sets the protocol of the request, e.g. `HTTP/1.1`. An empty protocol isn't inserted.
+    pub fn server_protocol(mut self, protocol: &str) -> ParamsBuilder<Build, R> {
+        if let Some(nvp) = NameValuePair::new("SERVER_PROTOCOL", protocol.to_owned()) {
+            self.inner = self.inner.insert_nvp(nvp);
+        }
+
+        S::transmute_once(self)
+    }
+
+    /// Sets the name and version of the server forwarding the request, e.g. `nginx/1.25.3`.
+    ///
+    /// An empty name isn't inserted, e.g. for gateways which don't disclose their software.
+    pub fn server_software(mut self, software: &str) -> ParamsBuilder<Build, R> {
+        if let Some(nvp) = NameValuePair::new("SERVER_SOFTWARE", software.to_owned()) {
+            self.inner = self.inner.insert_nvp(nvp);
+        }
+
+        S::transmute_once(self)
+    }
+
+    /// Inserts the params which virtually every application expects: `GATEWAY_INTERFACE` and
+    /// `SERVER_SOFTWARE`.
+    ///
+    /// Most frameworks additionally require `REQUEST_METHOD`, `SERVER_PROTOCOL`, `SERVER_PORT`
+    /// and `SERVER_NAME` (e.g. Django), and PHP-FPM requires `SCRIPT_FILENAME` to locate the
+    /// script to execute.
+    pub fn with_defaults(self, server_software: &str) -> ParamsBuilder<Build, R> {
+        self.gateway_interface().server_software(server_software)
+    }
+
+    /// Sets the method of the request, e.g. `GET` or `POST`. An empty method isn't inserted.
+    pub fn request_method(mut self, method: &str) -> ParamsBuilder<Build, R> {
+        if let Some(nvp) = NameValuePair::new("REQUEST_METHOD", method.to_owned()) {
+            self.inner = self.inner.insert_nvp(nvp);
+        }
+
+        S::transmute_once(self)
+    }
+
+    /// Sets the path of the script to execute, which PHP-FPM requires.
+    ///
+    /// An empty path isn't inserted, which PHP-FPM answers with a `File not found.` response.
+    pub fn script_filename(mut self, path: &str) -> ParamsBuilder<Build, R> {
+        if let Some(nvp) = NameValuePair::new("SCRIPT_FILENAME", path.to_owned()) {
+            self.inner = self.inner.insert_nvp(nvp);
+        }
+
+        S::transmute_once(self)
+    }
+
+    /// Sets the length of the body of the request, which is sent as stdin.
+    pub fn content_length(mut self, length: u64) -> ParamsBuilder<Build, R> {
+        let nvp = NameValuePair::new("CONTENT_LENGTH", length.to_string()).unwrap();
+        self.inner = self.inner.insert_nvp(nvp);
+
+        S::transmute_once(self)
+    }
+
+    /// Sets the CGI version to `CGI/1.1`, which is the only version in use.
+    pub fn gateway_interface(mut self) -> ParamsBuilder<Build, R> {
+        let nvp = NameValuePair::new("GATEWAY_INTERFACE", "CGI/1.1").unwrap();
+        self.inner = self.inner.insert_nvp(nvp);
+
+        S::transmute_once(self)
+    }
+
+    /// Sets the scheme of the request, e.g. `http` or `https`, unless it's empty.
+    pub fn request_scheme(mut self, scheme: &str) -> ParamsBuilder<Build, R> {
+        if let Some(nvp) = NameValuePair::new("REQUEST_SCHEME", scheme.to_owned()) {
+            self.inner = self.inner.insert_nvp(nvp);
+        }
+
+        S::transmute_once(self)
+    }
+
+    /// Sets `HTTPS` to `on` or `off`.
+    pub fn https(mut self, on: bool) -> ParamsBuilder<Build, R> {
+        let nvp = NameValuePair::new("HTTPS", if on { "on" } else { "off" }).unwrap();
+        self.inner = self.inner.insert_nvp(nvp);
+
+        S::transmute_once(self)
+    }
+
+    /// Sets the part of the path which follows the script name.
+    ///
+    /// An empty path isn't inserted, as it's equivalent to an absent `PATH_INFO`.
+    pub fn path_info(mut self, path: &str) -> ParamsBuilder<Build, R> {
+        if let Some(nvp) = NameValuePair::new("PATH_INFO", path.to_owned()) {
+            self.inner = self.inner.insert_nvp(nvp);
+        }
+
+        S::transmute_once(self)
+    }
+
+    /// Sets `SCRIPT_FILENAME`, `SCRIPT_NAME` and `PATH_INFO` from the document root and the
+    /// request URI.
+    ///
+    /// The path of the URI is split after the first segment with an extension which is followed
+    /// by more segments, e.g. `/index.php/extra/path` results in the script name `/index.php` and
+    /// the path info `/extra/path`. If there's no such segment, the whole path is the script
+    /// name. The query string is ignored, and the path isn't percent-decoded.
+    pub fn script_path(self, root: &str, uri: &str) -> ParamsBuilder<Build, R> {
+        let path = uri.split(['?', '#']).next().unwrap_or_default();
+        let path = if path.starts_with('/') {
+            path.to_owned()
+        } else {
+            format!("/{path}")
+        };
+
+        let (script_name, path_info) = split_script_path(&path);
+        let script_filename = format!("{}{}", root.trim_end_matches('/'), script_name);
+
+        let mut builder = self.script_filename(&script_filename);
+
+        let nvp = NameValuePair::new("SCRIPT_NAME", script_name.to_owned()).unwrap();
+        builder.inner = builder.inner.insert_nvp(nvp);
+
+        builder.path_info(path_info)
+    }
+}
+
+/// Splits the path after the first segment with an extension which is followed by a `/`.
+fn split_script_path(path: &str) -> (&str, &str) {
+    let mut end = 0;
+
+    for segment in path.split_inclusive('/') {
+        end += segment.len();
+
+        let Some(name) = segment.strip_suffix('/') else {
+            break;
+        };
+
+        // Hidden names such as `.well-known` don't have an extension.
+        if name.rfind('.').is_some_and(|i| i > 0 && i + 1 < name.len()) {
+            return path.split_at(end - 1);
+        }
+    }
+
+    (path, "")
 }
 
 impl<S: BuilderState> ParamsBuilder<S, Filter> {
-    /// Automatically added when setting the data in the request.
+    /// Automatically added when setting the data in the request. The names of both data params
+    /// are defined in section 6.4 of the FastCGI specification, including the `FCGI_` prefix.
     pub(crate) fn data_last_mod(
         mut self,
         data_last_mod: SystemTime,
@@ -137,3 +337,144 @@ impl<R: RoleTyped> Default for ParamsBuilder<Init, R> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::request::Responder;
+
+    use super::*;
+
+    #[test]
+    fn cgi_meta_variables() {
+        let params = Params::builder::<Responder>()
+            .server_protocol("HTTP/1.1")
+            .gateway_interface()
+            .request_scheme("https")
+            .https(true)
+            .path_info("/users/1")
+            .build();
+
+        let expected = [
+            ("SERVER_PROTOCOL", "HTTP/1.1"),
+            ("GATEWAY_INTERFACE", "CGI/1.1"),
+            ("REQUEST_SCHEME", "https"),
+            ("HTTPS", "on"),
+            ("PATH_INFO", "/users/1"),
+        ]
+        .into_iter()
+        .map(|(name, value)| NameValuePair::new(name, value).unwrap())
+        .collect();
+
+        assert_eq!(params, Params::new(expected));
+    }
+
+    fn nvps(pairs: &[(&'static str, &'static str)]) -> Params {
+        let pairs = pairs
+            .iter()
+            .map(|&(name, value)| NameValuePair::new(name, value).unwrap())
+            .collect();
+
+        Params::new(pairs)
+    }
+
+    #[test]
+    fn script_path_with_path_info() {
+        let expected = nvps(&[
+            ("SCRIPT_FILENAME", "/var/www/index.php"),
+            ("SCRIPT_NAME", "/index.php"),
+            ("PATH_INFO", "/extra/path"),
+        ]);
+
+        let params = Params::builder::<Responder>()
+            .script_path("/var/www/", "/index.php/extra/path?a=1")
+            .build();
+        assert_eq!(params, expected);
+
+        let params = Params::builder::<Responder>()
+            .script_path("/var/www", "index.php/extra/path")
+            .build();
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn script_path_without_path_info() {
+        let params = Params::builder::<Responder>()
+            .script_path("/var/www", "/blog/index.php?a=/b.php/c")
+            .build();
+
+        let expected = nvps(&[
+            ("SCRIPT_FILENAME", "/var/www/blog/index.php"),
+            ("SCRIPT_NAME", "/blog/index.php"),
+        ]);
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn script_path_splits_at_first_extension() {
+        let params = Params::builder::<Responder>()
+            .script_path("/var/www", "/.well-known/app.php/a.php/")
+            .build();
+
+        let expected = nvps(&[
+            ("SCRIPT_FILENAME", "/var/www/.well-known/app.php"),
+            ("SCRIPT_NAME", "/.well-known/app.php"),
+            ("PATH_INFO", "/a.php/"),
+        ]);
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn empty_value_round_trip() {
+        let mut params = Params::builder::<Responder>()
+            .server_port(80)
+            .build()
+            .insert_nvp(NameValuePair::new_empty("QUERY_STRING").unwrap());
+        let expected = params.clone();
+
+        let mut buffer = BytesMut::new();
+        params.inner.encode_chunk(&mut buffer).unwrap().unwrap();
+
+        assert_eq!(Params::decode_frame(buffer), Ok(expected));
+    }
+
+    #[test]
+    fn empty_values_are_skipped() {
+        let params = Params::builder::<Responder>()
+            .server_protocol("")
+            .server_software("")
+            .request_scheme("")
+            .gateway_interface()
+            .build();
+
+        assert_eq!(params, nvps(&[("GATEWAY_INTERFACE", "CGI/1.1")]));
+    }
+
+    #[test]
+    fn defaults() {
+        let params = Params::builder::<Responder>()
+            .with_defaults("nginx/1.25.3")
+            .build();
+
+        let expected = NameValuePairs::new()
+            .insert_nvp(NameValuePair::new("GATEWAY_INTERFACE", "CGI/1.1").unwrap())
+            .insert_nvp(NameValuePair::new("SERVER_SOFTWARE", "nginx/1.25.3").unwrap());
+
+        assert_eq!(params, Params::new(expected));
+    }
+
+    #[test]
+    fn parse_param() {
+        let params = Params::builder::<Responder>()
+            .server_port(8080)
+            .server_protocol("HTTP/1.1")
+            .build();
+
+        assert_eq!(params.get("SERVER_PORT"), Some(&b"8080"[..]));
+        assert_eq!(params.parse_param::<u16>("SERVER_PORT"), Ok(Some(8080)));
+        assert_eq!(params.parse_param::<u16>("REMOTE_PORT"), Ok(None));
+        assert_eq!(
+            params.parse_param::<u16>("SERVER_PROTOCOL"),
+            Err(FromParamsError::InvalidParam("SERVER_PROTOCOL"))
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Deref;
+
 use bytes::{Bytes, BytesMut};
 
 use crate::codec::Buffer;
@@ -68,6 +70,20 @@ impl AsRef<Bytes> for Stdout {
     }
 }
 
+impl AsRef<[u8]> for Stdout {
+    fn as_ref(&self) -> &[u8] {
+        self.0.bytes()
+    }
+}
+
+impl Deref for Stdout {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.0.bytes()
+    }
+}
+
 // Stderr
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -99,3 +115,17 @@ impl AsRef<Bytes> for Stderr {
         self.0.as_ref()
     }
 }
+
+impl AsRef<[u8]> for Stderr {
+    fn as_ref(&self) -> &[u8] {
+        self.0.bytes()
+    }
+}
+
+impl Deref for Stderr {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.0.bytes()
+    }
+}
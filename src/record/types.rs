@@ -55,7 +55,9 @@ impl From<u8> for RecordType {
     fn from(value: u8) -> Self {
         match value {
             1..=11 => Self::Standard(value.into()),
-            _ => Self::Custom(value.into()),
+            // Record types are received from the network, so constructing them must not panic.
+            // Reserved values such as 0 are rejected by the parsers instead.
+            _ => Self::Custom(Custom { record_type: value }),
         }
     }
 }
@@ -55,6 +55,7 @@ impl DecodeFrame for UnknownType {
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
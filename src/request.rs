@@ -1,23 +1,30 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
+use bytes::{Bytes, BytesMut};
+use futures::{executor::block_on, sink, stream, Sink, SinkExt, Stream, StreamExt};
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::Decoder;
 
 use crate::{
     await_variant, build_enum_with_from_impls,
+    codec::{FastCgiCodec, Frame},
     conn::{
         connection::{Connection, ConnectionRecvError, ConnectionSendError},
+        defrag::Defragmenter,
         endpoint, ParseRequestError,
     },
-    meta::DynRequestMetaExt,
     record::{
-        begin_request, params, AbortRequest, BeginRequest, Data, EndOfStream, GetValues, Id,
-        IntoRecord, Params, ParamsBuilder, Stdin,
+        begin_request, params, AbortRequest, BeginRequest, ByteSlice, Data, EndOfStream, Id,
+        IntoRecord, NameValuePair, NameValuePairs, Params, ParamsBuilder, Stdin,
+        DEFAULT_MAX_PAYLOAD_SIZE, HEADER_SIZE,
     },
 };
 
 #[derive(Debug)]
 pub struct Request {
+    id: Id,
     keep_conn: bool,
+    deadline: Option<Duration>,
     params: Params,
     stdin: Option<Stdin>,
     role: Role,
@@ -28,19 +35,130 @@ impl Request {
         RequestBuilder::new()
     }
 
-    pub(crate) async fn send<T: AsyncWrite + Unpin>(
+    /// Creates a Responder request with the `GET` method for the given script.
+    ///
+    /// ```
+    /// # use fastcgi::request::Request;
+    /// let request = Request::get("/var/www/index.php");
+    /// ```
+    pub fn get(script_filename: &str) -> Request {
+        let params = Params::builder::<Responder>()
+            .request_method("GET")
+            .script_filename(script_filename);
+
+        Request::builder().params(params).build()
+    }
+
+    /// Creates a Responder request with the `POST` method for the given script, which sends
+    /// the body as stdin.
+    ///
+    /// ```
+    /// # use fastcgi::request::Request;
+    /// let request = Request::post("/var/www/form.php", "name=value");
+    /// ```
+    pub fn post(script_filename: &str, body: impl Into<Bytes>) -> Request {
+        let body = body.into();
+
+        let params = Params::builder::<Responder>()
+            .request_method("POST")
+            .script_filename(script_filename)
+            .content_length(body.len() as u64);
+
+        let mut builder = Request::builder();
+
+        // An empty body is sent as an empty stdin stream.
+        if let Some(stdin) = ByteSlice::new(body) {
+            builder = builder.stdin(Stdin(stdin));
+        }
+
+        builder.params(params).build()
+    }
+
+    /// Encodes the request into the sequence of frames which a client would send for it,
+    /// including the empty frames which terminate its streams.
+    ///
+    /// This doesn't require a connection, which makes it useful for snapshot testing of the wire
+    /// format, or for sending requests over a custom transport. Synchronous, like
+    /// [`Request::parse`].
+    pub fn into_frames(self) -> Result<Vec<Frame>, ConnectionSendError> {
+        let mut connection = Connection::<_, endpoint::Client>::new(Vec::new());
+
+        block_on(self.send(&mut connection))?;
+
+        let mut src = BytesMut::from(&connection.into_transport()[..]);
+        let mut codec = FastCgiCodec::new();
+        let mut frames = Vec::new();
+
+        while let Some(frame) = codec
+            .decode(&mut src)
+            .expect("The frames were encoded by the same codec, so decoding them can't fail.")
+        {
+            frames.push(frame);
+        }
+
+        Ok(frames)
+    }
+
+    /// Decodes a request from the bytes which a client sent for it, e.g. as captured from a
+    /// connection. Returns `None` if the request was aborted.
+    ///
+    /// This is the counterpart of [`Request::into_frames`], and collects all the parts of a
+    /// request into one value, which is useful for testing servers without a connection. The
+    /// params, stdin, data and role are available through the getters of the request.
+    ///
+    /// Runs synchronously, as it only reads from memory, so no executor is needed.
+    pub fn parse(src: &[u8]) -> Result<Option<Request>, ConnectionRecvError<ParseRequestError>> {
+        let mut connection = Connection::<_, endpoint::Server>::new(src);
+
+        block_on(Self::recv(&mut connection))
+    }
+
+    /// Returns an estimate of the number of bytes the request occupies on the wire, including
+    /// record headers, padding, and the empty records which terminate its streams.
+    ///
+    /// This is an estimate, as the exact framing and padding depend on how the streams are split
+    /// into records when the request is sent.
+    pub fn encoded_size_hint(&self) -> usize {
+        // The body of a BeginRequest is always 8 bytes.
+        let begin_request = HEADER_SIZE + 8;
+        let params = stream_size_hint(self.params.size_hint());
+        let stdin = stream_size_hint(self.stdin.as_ref().map_or(0, |stdin| stdin.0.bytes().len()));
+        let data = self
+            .get_data()
+            .map_or(0, |data| stream_size_hint(data.length() as usize));
+
+        begin_request + params + stdin + data
+    }
+
+    pub(crate) async fn send<T: AsyncWrite + Unpin, D: Defragmenter>(
+        self,
+        connection: &mut Connection<T, endpoint::Client<D>>,
+    ) -> Result<SentLengths, ConnectionSendError> {
+        self.send_with_stdin(connection, stream::empty()).await
+    }
+
+    /// Sends the request, followed by the chunks of `stdin` as they become available.
+    ///
+    /// The stdin of the request itself, if any, is sent first. The stdin stream is ended once
+    /// `stdin` is exhausted.
+    pub(crate) async fn send_with_stdin<T, D, S>(
         self,
-        connection: &mut Connection<T, endpoint::Client>,
-    ) -> Result<(), ConnectionSendError> {
-        // Available Id should be received from the connection.
-        let id = 1;
+        connection: &mut Connection<T, endpoint::Client<D>>,
+        stdin: S,
+    ) -> Result<SentLengths, ConnectionSendError>
+    where
+        T: AsyncWrite + Unpin,
+        D: Defragmenter,
+        S: Stream<Item = Bytes> + Unpin,
+    {
+        let id = self.id;
 
         let begin_request =
             BeginRequest::from_parts((&self.role).into(), self.keep_conn).into_record(id);
 
         connection.feed_frame(begin_request).await?;
 
-        let result = self.send_inner(id, connection).await;
+        let result = self.send_inner(id, connection, stdin).await;
 
         // Attempt to send an abort request on error.
         if result.is_err() {
@@ -53,42 +171,98 @@ impl Request {
         result
     }
 
-    async fn send_inner<T: AsyncWrite + Unpin>(
+    async fn send_inner<T, D, S>(
         self,
         id: Id,
-        connection: &mut Connection<T, endpoint::Client>,
-    ) -> Result<(), ConnectionSendError> {
+        connection: &mut Connection<T, endpoint::Client<D>>,
+        stdin: S,
+    ) -> Result<SentLengths, ConnectionSendError>
+    where
+        T: AsyncWrite + Unpin,
+        D: Defragmenter,
+        S: Stream<Item = Bytes> + Unpin,
+    {
+        let mut sent = SentLengths::default();
+
         connection.feed_stream(self.params.into_record(id)).await?;
 
-        if let Some(stdin) = self.stdin {
-            connection.feed_stream(stdin.into_record(id)).await?;
-        } else {
-            let eof = EndOfStream::<Stdin>::new().into_record(id);
-            connection.feed_empty(eof).await?;
+        let mut stdin = stream::iter(self.stdin.map(|stdin| stdin.0.bytes().clone())).chain(stdin);
+
+        while let Some(bytes) = stdin.next().await {
+            // An empty record would end the stream early.
+            if let Some(bytes) = ByteSlice::new(bytes) {
+                sent.stdin += connection
+                    .feed_stream_chunk(Stdin(bytes).into_record(id))
+                    .await?;
+            }
         }
 
+        let eof = EndOfStream::<Stdin>::new().into_record(id);
+        connection.feed_empty(eof).await?;
+
         if let Role::Filter(data) = self.role {
-            connection.feed_stream(data.into_record(id)).await?;
+            sent.data = connection.feed_stream(data.into_record(id)).await?;
         }
 
-        Ok(())
+        Ok(sent)
     }
 
-    pub(crate) async fn recv<T: AsyncRead + Unpin>(
-        connection: &mut Connection<T, endpoint::Server>,
+    pub(crate) async fn recv<T: AsyncRead + Unpin, D: Defragmenter>(
+        connection: &mut Connection<T, endpoint::Server<D>>,
     ) -> Result<Option<Self>, ConnectionRecvError<ParseRequestError>> {
+        Self::recv_streaming(connection, |_| sink::drain()).await
+    }
+
+    /// Receives a request, forwarding each param to the sink as soon as it's received if the
+    /// stream was opened in streaming mode. The sink is created from the role of the request,
+    /// once its `BeginRequest` is received.
+    pub(crate) async fn recv_streaming<T, D, S>(
+        connection: &mut Connection<T, endpoint::Server<D>>,
+        route: impl FnOnce(begin_request::Role) -> S,
+    ) -> Result<Option<Self>, ConnectionRecvError<ParseRequestError>>
+    where
+        T: AsyncRead + Unpin,
+        D: Defragmenter,
+        S: Sink<NameValuePair> + Unpin,
+        S::Error: std::error::Error + Send + Sync + 'static,
+    {
         // A channel should be used here instead which receives request parts
         // based on the request id.
 
         // The stream state guarantees that none of the expects and unreachable! can fail.
 
-        let begin_request = loop {
-            if let Some(result) = connection.poll_frame().await {
-                break BeginRequest::try_from(result?).expect("An unexpected error occured.");
+        let begin_request = BeginRequest::try_from(connection.recv_part().await?)
+            .expect("An unexpected error occured.");
+
+        let id = connection
+            .stream_id()
+            .expect("The stream received a BeginRequest.");
+
+        let mut sink = route(begin_request.get_role());
+
+        let params = loop {
+            match connection.recv_part().await? {
+                Part::ParamsChunk(nvps) => {
+                    for nvp in nvps {
+                        sink.feed(nvp)
+                            .await
+                            .map_err(|e| ConnectionRecvError::SinkError(Box::new(e)))?;
+                    }
+
+                    sink.flush()
+                        .await
+                        .map_err(|e| ConnectionRecvError::SinkError(Box::new(e)))?;
+                }
+                Part::Params(params) => break params,
+                Part::AbortRequest => {
+                    connection.close_stream();
+
+                    return Ok(None);
+                }
+                _ => unreachable!(),
             }
         };
 
-        let params = await_variant!(connection, Part::Params);
         let stdin = await_variant!(connection, Part::Stdin);
 
         let role = match begin_request.get_role() {
@@ -102,6 +276,8 @@ impl Request {
         };
 
         Ok(Some(Request {
+            id,
+            deadline: None,
             keep_conn: begin_request.get_keep_conn(),
             params,
             stdin,
@@ -109,6 +285,16 @@ impl Request {
         }))
     }
 
+    /// Returns the id of the request, which is used to correlate it with its response.
+    pub fn get_id(&self) -> Id {
+        self.id
+    }
+
+    /// Returns the time the client waits for the response, if a deadline was set.
+    pub fn get_deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
     pub fn get_keep_conn(&self) -> bool {
         self.keep_conn
     }
@@ -132,10 +318,32 @@ impl Request {
             None
         }
     }
+}
 
-    pub(crate) fn into_parts(self) -> (bool, Params, Option<Stdin>, Role) {
-        (self.keep_conn, self.params, self.stdin, self.role)
-    }
+/// The number of bytes of the streams of a request which were sent.
+///
+/// These may differ from the lengths which were declared up front, e.g. when the reader of the
+/// data of a filter request ends before `FCGI_DATA_LENGTH` bytes were read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SentLengths {
+    pub stdin: u64,
+    pub data: u64,
+}
+
+/// Estimates the size of a stream of `length` bytes, split into records of the maximum payload
+/// size, each padded to a multiple of 8 bytes, and followed by an empty record.
+fn stream_size_hint(length: usize) -> usize {
+    let full_records = length / DEFAULT_MAX_PAYLOAD_SIZE;
+    let remainder = length % DEFAULT_MAX_PAYLOAD_SIZE;
+
+    let full = full_records * (HEADER_SIZE + DEFAULT_MAX_PAYLOAD_SIZE.next_multiple_of(8));
+    let last = if remainder > 0 {
+        HEADER_SIZE + remainder.next_multiple_of(8)
+    } else {
+        0
+    };
+
+    full + last + HEADER_SIZE
 }
 
 #[derive(Debug)]
@@ -174,9 +382,7 @@ pub trait RoleTyped: sealed::Sealed {}
 pub struct Responder;
 pub struct Authorizer;
 
-pub struct Filter {
-    data: Data,
-}
+pub struct Filter;
 
 impl RoleTyped for Responder {}
 impl RoleTyped for Authorizer {}
@@ -199,9 +405,19 @@ impl BuilderState for Init {}
 impl<R: RoleTyped> BuilderState for ParamsSet<R> {}
 impl BuilderState for FilterSelected {}
 
+/// The param in which the deadline of a request is sent, unless configured otherwise.
+pub const DEFAULT_DEADLINE_PARAM: &str = "FCGI_REQUEST_DEADLINE";
+
+#[derive(Debug)]
+struct Deadline {
+    timeout: Duration,
+    param: String,
+}
+
 pub struct RequestBuilder<S: BuilderState> {
     keep_conn: bool,
     stdin: Option<Stdin>,
+    deadline: Option<Deadline>,
     state: S,
 }
 
@@ -221,9 +437,59 @@ impl<S: BuilderState> RequestBuilder<S> {
         self.stdin = Some(stdin);
         self
     }
+
+    /// Sets the time the client waits for the response, after which the request is aborted.
+    ///
+    /// The timeout is also sent to the server in milliseconds, in the
+    /// [`DEFAULT_DEADLINE_PARAM`] param, so servers which honor it can stop early.
+    pub fn deadline(self, timeout: Duration) -> Self {
+        self.deadline_with_param(DEFAULT_DEADLINE_PARAM, timeout)
+    }
+
+    /// Same as [`RequestBuilder::deadline`], but sends the timeout in the given param, as the
+    /// name isn't standardized. An empty name falls back to [`DEFAULT_DEADLINE_PARAM`].
+    pub fn deadline_with_param(mut self, param: &str, timeout: Duration) -> Self {
+        let param = if param.is_empty() {
+            DEFAULT_DEADLINE_PARAM
+        } else {
+            param
+        };
+
+        self.deadline = Some(Deadline {
+            timeout,
+            param: param.to_owned(),
+        });
+        self
+    }
+
+    fn build_params<R: RoleTyped>(
+        deadline: &Option<Deadline>,
+        params: ParamsBuilder<params::Build, R>,
+    ) -> Params {
+        let params = params.build();
+
+        match deadline {
+            Some(deadline) => {
+                let timeout = deadline.timeout.as_millis().to_string();
+
+                params.insert_nvp(NameValuePair::new(deadline.param.clone(), timeout).unwrap())
+            }
+            None => params,
+        }
+    }
 }
 
 impl RequestBuilder<Init> {
+    /// Sets the params of the request, which also selects its role.
+    ///
+    /// Params can't be empty, so at least one param must be set on the builder:
+    ///
+    /// ```compile_fail
+    /// # use fastcgi::{record::ParamsBuilder, request::{Request, Responder}};
+    /// let params = ParamsBuilder::<_, Responder>::new();
+    ///
+    /// let request = Request::builder().params(params).build();
+    /// ```
     pub fn params<R: RoleTyped>(
         self,
         params: ParamsBuilder<params::Build, R>,
@@ -231,12 +497,34 @@ impl RequestBuilder<Init> {
         RequestBuilder {
             keep_conn: self.keep_conn,
             stdin: self.stdin,
+            deadline: self.deadline,
             state: ParamsSet { params },
         }
     }
 }
 
 impl RequestBuilder<ParamsSet<Filter>> {
+    /// Sets the data of a filter request, which is required before the request can be built.
+    ///
+    /// ```
+    /// # use std::time::SystemTime;
+    /// # use fastcgi::{record::{Data, Params}, request::{Filter, Request}};
+    /// let params = Params::builder::<Filter>().server_port(80);
+    ///
+    /// let request = Request::builder()
+    ///     .params(params)
+    ///     .data(Data::from("data"), SystemTime::now())
+    ///     .build();
+    /// ```
+    ///
+    /// Building a filter request without its data doesn't compile:
+    ///
+    /// ```compile_fail
+    /// # use fastcgi::{record::Params, request::{Filter, Request}};
+    /// let params = Params::builder::<Filter>().server_port(80);
+    ///
+    /// let request = Request::builder().params(params).build();
+    /// ```
     pub fn data(
         mut self,
         data: Data,
@@ -248,6 +536,7 @@ impl RequestBuilder<ParamsSet<Filter>> {
         RequestBuilder {
             keep_conn: self.keep_conn,
             stdin: self.stdin,
+            deadline: self.deadline,
             state: FilterSelected {
                 params: self.state.params,
                 data,
@@ -259,7 +548,10 @@ impl RequestBuilder<ParamsSet<Filter>> {
 impl RequestBuilder<ParamsSet<Responder>> {
     pub fn build(self) -> Request {
         Request {
-            params: self.state.params.build(),
+            // Available Id should be received from the connection.
+            id: 1,
+            params: Self::build_params(&self.deadline, self.state.params),
+            deadline: self.deadline.map(|deadline| deadline.timeout),
             stdin: self.stdin,
             role: Role::Responder,
             keep_conn: self.keep_conn,
@@ -270,7 +562,10 @@ impl RequestBuilder<ParamsSet<Responder>> {
 impl RequestBuilder<ParamsSet<Authorizer>> {
     pub fn build(self) -> Request {
         Request {
-            params: self.state.params.build(),
+            // Available Id should be received from the connection.
+            id: 1,
+            params: Self::build_params(&self.deadline, self.state.params),
+            deadline: self.deadline.map(|deadline| deadline.timeout),
             stdin: self.stdin,
             role: Role::Authorizer,
             keep_conn: self.keep_conn,
@@ -281,7 +576,10 @@ impl RequestBuilder<ParamsSet<Authorizer>> {
 impl RequestBuilder<FilterSelected> {
     pub fn build(self) -> Request {
         Request {
-            params: self.state.params.build(),
+            // Available Id should be received from the connection.
+            id: 1,
+            params: Self::build_params(&self.deadline, self.state.params),
+            deadline: self.deadline.map(|deadline| deadline.timeout),
             stdin: self.stdin,
             role: Role::Filter(self.state.data),
             keep_conn: self.keep_conn,
@@ -294,6 +592,7 @@ impl Default for RequestBuilder<Init> {
         Self {
             keep_conn: false,
             stdin: None,
+            deadline: None,
             state: Init,
         }
     }
@@ -304,18 +603,324 @@ build_enum_with_from_impls! {
         BeginRequest(BeginRequest),
         AbortRequest,
         Params(Params),
+        ParamsChunk(NameValuePairs),
         Stdin(Option<Stdin>),
         Data(Data),
     }
 }
 
-enum ManagementRequest {
-    GetValues(GetValues),
-    Custom(Box<dyn DynRequestMetaExt>),
-}
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use bytes::Bytes;
+
+    use crate::{
+        conn::Config,
+        record::{ByteSlice, RecordType, Standard},
+    };
+
+    use super::*;
+
+    fn record_types(frames: &[Frame]) -> Vec<(RecordType, bool)> {
+        frames
+            .iter()
+            .map(|frame| {
+                let (_, record_type, payload) = frame.as_parts();
+                (record_type, payload.is_empty())
+            })
+            .collect()
+    }
+
+    fn encode(request: Request) -> Vec<u8> {
+        let mut connection = Connection::<_, endpoint::Client>::new(Vec::new());
+        block_on(request.send(&mut connection)).unwrap();
+
+        connection.into_transport()
+    }
+
+    fn encoded_size(request: Request) -> usize {
+        encode(request).len()
+    }
+
+    #[test]
+    fn encoded_size_hint() {
+        let params = Params::builder::<Responder>()
+            .server_port(80)
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+
+        let stdin = Stdin(ByteSlice::new(Bytes::from(vec![0; 200_000])).unwrap());
+
+        let request = Request::builder().params(params).stdin(stdin).build();
+        let hint = request.encoded_size_hint();
+
+        assert_eq!(hint, encoded_size(request));
+    }
+
+    #[test]
+    fn responder_into_frames() {
+        let params = Params::builder::<Responder>()
+            .server_port(80)
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+
+        let stdin = Stdin(ByteSlice::new(Bytes::from_static(b"body")).unwrap());
+
+        let frames = Request::builder()
+            .params(params)
+            .stdin(stdin)
+            .build()
+            .into_frames()
+            .unwrap();
+
+        assert_eq!(
+            record_types(&frames),
+            [
+                (Standard::BeginRequest.into(), false),
+                (Standard::Params.into(), false),
+                (Standard::Params.into(), true),
+                (Standard::Stdin.into(), false),
+                (Standard::Stdin.into(), true),
+            ]
+        );
+
+        let (id, _, payload) = frames[3].as_parts();
+        assert_eq!(id, 1);
+        assert_eq!(&payload[..], b"body");
+    }
+
+    #[test]
+    fn filter_into_frames() {
+        let params = Params::builder::<Filter>().server_port(80);
+
+        let frames = Request::builder()
+            .params(params)
+            .data(Data::from("data"), SystemTime::UNIX_EPOCH)
+            .build()
+            .into_frames()
+            .unwrap();
+
+        // The data stream is closed with an empty record, which filter applications require.
+        assert_eq!(
+            record_types(&frames),
+            [
+                (Standard::BeginRequest.into(), false),
+                (Standard::Params.into(), false),
+                (Standard::Params.into(), true),
+                (Standard::Stdin.into(), true),
+                (Standard::Data.into(), false),
+                (Standard::Data.into(), true),
+            ]
+        );
+
+        let (_, _, payload) = frames[4].as_parts();
+        assert_eq!(&payload[..], b"data");
+    }
+
+    #[test]
+    fn filter_data_params() {
+        let params = Params::builder::<Filter>().server_port(80);
+
+        let request = Request::builder()
+            .params(params)
+            .data(
+                Data::from("data"),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            )
+            .build();
+
+        let params = request.get_params();
+        assert_eq!(params.get("FCGI_DATA_LAST_MOD"), Some(&b"1700000000"[..]));
+        assert_eq!(params.get("FCGI_DATA_LENGTH"), Some(&b"4"[..]));
+    }
+
+    #[test]
+    fn parse_sent_request() {
+        let params = Params::builder::<Filter>().server_port(80);
+        let stdin = Stdin(ByteSlice::new(Bytes::from_static(b"body")).unwrap());
+
+        let request = Request::builder()
+            .params(params)
+            .stdin(stdin)
+            .data(Data::from("data"), SystemTime::UNIX_EPOCH)
+            .build();
+        let params = request.get_params().clone();
+
+        let mut connection = Connection::<_, endpoint::Client>::new(Vec::new());
+        block_on(request.send(&mut connection)).unwrap();
+
+        let request = Request::parse(&connection.into_transport())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(request.get_params(), &params);
+        assert_eq!(request.get_stdin().as_ref().unwrap().0.bytes(), "body");
+        assert_eq!(
+            request.get_data().and_then(Data::byte_slice),
+            Some(&Bytes::from("data"))
+        );
+    }
+
+    #[test]
+    fn get_and_post() {
+        let request = Request::parse(&encode(Request::get("/index.php")))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            request.get_params().get("REQUEST_METHOD"),
+            Some(&b"GET"[..])
+        );
+        assert_eq!(
+            request.get_params().get("SCRIPT_FILENAME"),
+            Some(&b"/index.php"[..])
+        );
+        assert!(request.get_stdin().is_none());
+
+        let request = Request::parse(&encode(Request::post("/form.php", "a=b")))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            request.get_params().get("REQUEST_METHOD"),
+            Some(&b"POST"[..])
+        );
+        assert_eq!(request.get_params().get("CONTENT_LENGTH"), Some(&b"3"[..]));
+        assert_eq!(request.get_stdin().as_ref().unwrap().0.bytes(), "a=b");
+    }
+
+    #[test]
+    fn get_without_script_filename() {
+        let request = Request::parse(&encode(Request::get(""))).unwrap().unwrap();
+
+        assert_eq!(request.get_params().get("SCRIPT_FILENAME"), None);
+    }
+
+    #[test]
+    fn owned_data_round_trip() {
+        for data in [
+            Data::from(b"vec".to_vec()),
+            Data::from(String::from("string")),
+        ] {
+            let expected = data.byte_slice().cloned();
+
+            let params = Params::builder::<Filter>().server_port(80);
+            let request = Request::builder()
+                .params(params)
+                .data(data, SystemTime::UNIX_EPOCH)
+                .build();
+
+            let request = Request::parse(&encode(request)).unwrap().unwrap();
+
+            assert_eq!(
+                request.get_data().and_then(Data::byte_slice),
+                expected.as_ref()
+            );
+        }
+    }
+
+    #[test]
+    fn sent_lengths() {
+        let params = Params::builder::<Filter>().server_port(80);
+        let stdin = Stdin(ByteSlice::new(Bytes::from(vec![0; 200_000])).unwrap());
+
+        // The reader ends before the declared length.
+        let data = Data::new_reader(&b"data"[..], 10);
+
+        let request = Request::builder()
+            .params(params)
+            .stdin(stdin)
+            .data(data, SystemTime::UNIX_EPOCH)
+            .build();
+
+        let mut connection = Connection::<_, endpoint::Client>::new(Vec::new());
+        let sent = block_on(request.send(&mut connection)).unwrap();
+
+        assert_eq!(
+            sent,
+            SentLengths {
+                stdin: 200_000,
+                data: 4
+            }
+        );
+    }
+
+    #[test]
+    fn stdin_is_split_at_max_frame_size() {
+        let params = Params::builder::<Responder>()
+            .server_port(80)
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+        let stdin = Stdin(ByteSlice::new(Bytes::from(vec![0; 10_000])).unwrap());
+
+        let request = Request::builder().params(params).stdin(stdin).build();
+
+        let config = Config::new().max_frame_size(4096);
+        let mut connection = Connection::<_, endpoint::Client>::with_config(Vec::new(), config);
+        block_on(request.send(&mut connection)).unwrap();
+
+        let mut src = BytesMut::from(&connection.into_transport()[..]);
+        let mut codec = FastCgiCodec::new();
+        let mut lengths = Vec::new();
+
+        while let Some(frame) = codec.decode(&mut src).unwrap() {
+            let (_, record_type, payload) = frame.as_parts();
+
+            if record_type == Standard::Stdin {
+                lengths.push(payload.len());
+            }
+        }
+
+        assert_eq!(lengths, [4096, 4096, 1808, 0]);
+    }
+
+    #[test]
+    fn received_request_has_id() {
+        let params = Params::builder::<Responder>()
+            .server_port(80)
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+
+        let mut request = Request::builder().params(params).build();
+        request.id = 3;
+
+        let mut connection = Connection::<_, endpoint::Client>::new(Vec::new());
+        block_on(request.send(&mut connection)).unwrap();
+        let src = connection.into_transport();
+
+        let mut connection = Connection::<_, endpoint::Server>::new(&src[..]);
+        let request = block_on(Request::recv(&mut connection)).unwrap().unwrap();
+
+        assert_eq!(request.get_id(), 3);
+    }
+
+    #[test]
+    fn deadline_is_sent_as_param() {
+        let params = Params::builder::<Responder>().server_port(80);
+
+        let request = Request::builder()
+            .params(params)
+            .deadline_with_param("X_DEADLINE", Duration::from_millis(1500))
+            .build();
+
+        let expected = Params::builder::<Responder>()
+            .server_port(80)
+            .build()
+            .insert_nvp(NameValuePair::new("X_DEADLINE", "1500").unwrap());
+
+        assert_eq!(request.get_params(), &expected);
+        assert_eq!(request.get_deadline(), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn empty_deadline_param_falls_back_to_default() {
+        let params = Params::builder::<Responder>().server_port(80);
+
+        let request = Request::builder()
+            .params(params)
+            .deadline_with_param("", Duration::from_millis(1500))
+            .build();
 
-impl From<Box<dyn DynRequestMetaExt>> for ManagementRequest {
-    fn from(value: Box<dyn DynRequestMetaExt>) -> Self {
-        ManagementRequest::Custom(value)
+        assert_eq!(
+            request.get_params().get(DEFAULT_DEADLINE_PARAM),
+            Some(&b"1500"[..])
+        );
     }
 }
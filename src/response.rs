@@ -1,16 +1,17 @@
+use bytes::Bytes;
+use futures::{sink, Sink, SinkExt};
 use tokio::io::{AsyncRead, AsyncWrite};
 
 use crate::{
     build_enum_with_from_impls,
+    codec::FrameStats,
     conn::{
         connection::{Connection, ConnectionRecvError, ConnectionSendError},
+        defrag::Defragmenter,
         endpoint, ParseResponseError,
     },
-    meta::DynResponseMetaExt,
-    record::{
-        EndOfStream, EndRequest, GetValuesResult, IntoRecord, ProtocolStatus, Stderr, Stdout,
-        UnknownType,
-    },
+    record::{ByteSlice, EndOfStream, EndRequest, Id, IntoRecord, ProtocolStatus, Stderr, Stdout},
+    request::SentLengths,
 };
 
 #[derive(Debug, Default)]
@@ -18,6 +19,17 @@ pub struct Response {
     stdout: Option<Stdout>,
     stderr: Option<Stderr>,
     app_status: u32,
+    sent: Option<SentLengths>,
+    metrics: Option<RequestMetrics>,
+}
+
+/// The frames which were exchanged for a request, including the frames of its response.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RequestMetrics {
+    pub sent: FrameStats,
+    pub received: FrameStats,
+    /// The most bytes of stdout and stderr which were held at once while reassembling them.
+    pub peak_defrag_size: usize,
 }
 
 impl Response {
@@ -25,13 +37,11 @@ impl Response {
         ResponseBuilder::new()
     }
 
-    pub(crate) async fn send<T: AsyncWrite + Unpin>(
+    pub(crate) async fn send<T: AsyncWrite + Unpin, D: Defragmenter>(
         self,
-        connection: &mut Connection<T, endpoint::Server>,
+        id: Id,
+        connection: &mut Connection<T, endpoint::Server<D>>,
     ) -> Result<(), ConnectionSendError> {
-        // Id should be received from the connection.
-        let id = 1;
-
         // TODO: Stdout and Stderr should be interleaved here.
         // Currently not possible due to &mut connection.
         if let Some(stdout) = self.stdout {
@@ -61,30 +71,65 @@ impl Response {
         Ok(())
     }
 
-    pub(crate) async fn recv<T: AsyncRead + Unpin>(
-        connection: &mut Connection<T, endpoint::Client>,
+    /// Ends the request without sending a response, e.g. when the server rejects it.
+    pub(crate) async fn send_rejection<T: AsyncWrite + Unpin, D: Defragmenter>(
+        id: Id,
+        protocol_status: ProtocolStatus,
+        connection: &mut Connection<T, endpoint::Server<D>>,
+    ) -> Result<(), ConnectionSendError> {
+        let end_request = EndRequest::new(0, protocol_status).into_record(id);
+        connection.feed_frame(end_request).await?;
+
+        connection.flush().await?;
+        connection.close_stream();
+
+        Ok(())
+    }
+
+    pub(crate) async fn recv<T: AsyncRead + Unpin, D: Defragmenter>(
+        connection: &mut Connection<T, endpoint::Client<D>>,
     ) -> Result<Self, ConnectionRecvError<ParseResponseError>> {
+        Self::recv_streaming(connection, sink::drain()).await
+    }
+
+    /// Receives a response, forwarding stdout chunks to the sink if the stream was opened in
+    /// streaming mode.
+    pub(crate) async fn recv_streaming<T, D, S>(
+        connection: &mut Connection<T, endpoint::Client<D>>,
+        mut sink: S,
+    ) -> Result<Self, ConnectionRecvError<ParseResponseError>>
+    where
+        T: AsyncRead + Unpin,
+        D: Defragmenter,
+        S: Sink<Bytes> + Unpin,
+        S::Error: std::error::Error + Send + Sync + 'static,
+    {
         let mut builder = Response::builder();
 
         let response = loop {
-            if let Some(result) = connection.poll_frame().await {
-                match result? {
-                    Part::Stdout(Some(stdout)) => builder = builder.stdout(stdout),
-                    Part::Stderr(Some(stderr)) => builder = builder.stderr(stderr),
-                    Part::EndRequest(end_request) => match end_request.get_protocol_status() {
-                        ProtocolStatus::RequestComplete => {
-                            let app_status = end_request.get_app_status();
-                            break builder.app_status(app_status).build();
-                        }
-                        status => {
-                            connection.close_stream();
-
-                            Err(status)?;
-                        }
-                    },
-                    _ => {
-                        // Ignore empty Stdout & Stderr
+            match connection.recv_part().await? {
+                Part::StdoutChunk(chunk) => sink
+                    .send(chunk)
+                    .await
+                    .map_err(|e| ConnectionRecvError::SinkError(Box::new(e)))?,
+                Part::Stdout(Some(stdout)) => builder = builder.stdout(stdout),
+                Part::Stderr(Some(stderr)) => builder = builder.stderr(stderr),
+                Part::EndRequest(end_request) => {
+                    break Self::end(connection, builder, end_request)?;
+                }
+                Part::PartialEndRequest((stdout, stderr, end_request)) => {
+                    if let Some(stdout) = stdout {
+                        builder = builder.stdout(stdout);
                     }
+
+                    if let Some(stderr) = stderr {
+                        builder = builder.stderr(stderr);
+                    }
+
+                    break Self::end(connection, builder, end_request)?;
+                }
+                _ => {
+                    // Ignore empty Stdout & Stderr
                 }
             }
         };
@@ -92,6 +137,33 @@ impl Response {
         Ok(response)
     }
 
+    fn end<T, D: Defragmenter>(
+        connection: &mut Connection<T, endpoint::Client<D>>,
+        builder: ResponseBuilder<Init>,
+        end_request: EndRequest,
+    ) -> Result<Self, ConnectionRecvError<ParseResponseError>> {
+        match end_request.get_protocol_status() {
+            ProtocolStatus::RequestComplete => {
+                let app_status = end_request.get_app_status();
+
+                Ok(builder.app_status(app_status).build())
+            }
+            status => {
+                connection.close_stream();
+
+                Err(status)?
+            }
+        }
+    }
+
+    /// Returns the combined size of stdout and stderr.
+    pub(crate) fn payload_size(&self) -> usize {
+        let stdout = self.stdout.as_ref().map_or(0, |stdout| stdout.len());
+        let stderr = self.stderr.as_ref().map_or(0, |stderr| stderr.len());
+
+        stdout + stderr
+    }
+
     pub fn get_stdout(&self) -> &Option<Stdout> {
         &self.stdout
     }
@@ -104,11 +176,57 @@ impl Response {
         self.app_status
     }
 
-    pub(crate) fn into_parts(self) -> (Option<Stdout>, Option<Stderr>, u32) {
-        (self.stdout, self.stderr, self.app_status)
+    /// Returns the number of stdin and data bytes which were sent for the request. Only set for
+    /// responses received by a client.
+    pub fn get_sent(&self) -> Option<SentLengths> {
+        self.sent
+    }
+
+    pub(crate) fn with_sent(mut self, sent: SentLengths) -> Self {
+        self.sent = Some(sent);
+        self
+    }
+
+    /// Returns the frames which were exchanged for the request. Only set for responses received
+    /// by a client with [`crate::conn::Config::request_metrics`] enabled.
+    pub fn get_metrics(&self) -> Option<RequestMetrics> {
+        self.metrics
+    }
+
+    pub(crate) fn with_metrics(mut self, metrics: RequestMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Returns stdout if the app status is 0, following the exit code convention of CGI
+    /// scripts, and the app status with stderr otherwise.
+    pub fn into_result(self) -> Result<Bytes, AppError> {
+        let bytes = |slice: &ByteSlice| slice.bytes().clone();
+
+        if self.app_status == 0 {
+            Ok(self
+                .stdout
+                .map(|stdout| bytes(&stdout.0))
+                .unwrap_or_default())
+        } else {
+            Err(AppError {
+                status: self.app_status,
+                stderr: self
+                    .stderr
+                    .map(|stderr| bytes(&stderr.0))
+                    .unwrap_or_default(),
+            })
+        }
     }
 }
 
+/// A response with a nonzero app status, see [`Response::into_result`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppError {
+    pub status: u32,
+    pub stderr: Bytes,
+}
+
 mod sealed {
     use super::*;
 
@@ -166,6 +284,8 @@ impl ResponseBuilder<StatusSet> {
             stdout: self.stdout,
             stderr: self.stderr,
             app_status: self.state.app_status,
+            sent: None,
+            metrics: None,
         }
     }
 }
@@ -182,20 +302,40 @@ impl Default for ResponseBuilder<Init> {
 
 build_enum_with_from_impls! {
     pub(crate) Part {
+        StdoutChunk(Bytes),
         Stdout(Option<Stdout>),
         Stderr(Option<Stderr>),
         EndRequest(EndRequest),
+        // The request was ended before all of its streams were.
+        PartialEndRequest((Option<Stdout>, Option<Stderr>, EndRequest)),
     }
 }
 
-enum ManagementResponse {
-    GetValuesResult(GetValuesResult),
-    UnknownType(UnknownType),
-    Custom(Box<dyn DynResponseMetaExt>),
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(app_status: u32) -> Response {
+        Response::builder()
+            .stdout(Stdout(ByteSlice::new(Bytes::from("out")).unwrap()))
+            .stderr(Stderr(ByteSlice::new(Bytes::from("err")).unwrap()))
+            .app_status(app_status)
+            .build()
+    }
+
+    #[test]
+    fn successful_response_into_result() {
+        assert_eq!(response(0).into_result(), Ok(Bytes::from("out")));
+    }
 
-impl From<Box<dyn DynResponseMetaExt>> for ManagementResponse {
-    fn from(value: Box<dyn DynResponseMetaExt>) -> Self {
-        ManagementResponse::Custom(value)
+    #[test]
+    fn failed_response_into_result() {
+        assert_eq!(
+            response(2).into_result(),
+            Err(AppError {
+                status: 2,
+                stderr: Bytes::from("err")
+            })
+        );
     }
 }
@@ -1,11 +1,15 @@
+use futures::Sink;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 use crate::{
     conn::{
+        config::Config,
         connection::{Connection, ConnectionRecvError, ConnectionSendError},
+        defrag::{Defrag, Defragmenter},
         endpoint,
-        state::server::ParseRequestError,
+        state::server::{self, ParseRequestError},
     },
+    record::{Id, NameValuePair, ProtocolStatus, Role},
     request::Request,
     response::Response,
     FastcgiServerError,
@@ -13,31 +17,69 @@ use crate::{
 
 /// TODO: design API.
 #[derive(Debug)]
-pub struct Server<T> {
-    connection: Connection<T, endpoint::Server>,
+pub struct Server<T, D: Defragmenter = Defrag> {
+    connection: Connection<T, endpoint::Server<D>>,
+    // The id of the request which was last returned by `next_request`.
+    current: Option<Id>,
 }
 
 impl<T: AsyncRead + AsyncWrite> Server<T> {
     pub fn new(transport: T) -> Self {
+        Self::with_defragmenter(transport, Config::default())
+    }
+
+    pub fn with_config(transport: T, config: Config) -> Self {
+        Self::with_defragmenter(transport, config)
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite, D: Defragmenter> Server<T, D> {
+    /// Creates a server which reassembles received streams with the defragmenter `D`.
+    pub fn with_defragmenter(transport: T, config: Config) -> Self {
         Self {
-            connection: Connection::new(transport),
+            connection: Connection::with_config(transport, config),
+            current: None,
         }
     }
 }
 
-impl<T: AsyncRead + AsyncWrite + Unpin> Server<T> {
+impl<T: AsyncRead + AsyncWrite + Unpin, D: Defragmenter> Server<T, D> {
     pub async fn handle_request(
         &mut self,
         f: impl Fn(Result<Request, FastcgiServerError>) -> Response,
     ) -> Result<(), FastcgiServerError> {
-        if let Some(result) = self.recv_request().await.transpose() {
+        let result = Request::recv(&mut self.connection).await;
+
+        self.handle_result(result, f).await
+    }
+
+    async fn handle_result(
+        &mut self,
+        result: Result<Option<Request>, ConnectionRecvError<ParseRequestError>>,
+        f: impl Fn(Result<Request, FastcgiServerError>) -> Response,
+    ) -> Result<(), FastcgiServerError> {
+        // The id is unknown if the request failed on its first frame.
+        let id = self.connection.stream_id().unwrap_or(1);
+
+        self.end_stream(result.is_err());
+
+        // Either the client is gone, or the byte stream can't be trusted anymore, so there's no
+        // one to respond to. The caller should close the connection.
+        match result {
+            Err(e) if is_connection_error(&e) => return Err(FastcgiServerError::Recv(e)),
+            _ => (),
+        }
+
+        self.send_rejections().await?;
+
+        if let Some(result) = result.transpose() {
             let result = result.map_err(|e| {
                 // TODO: log this.
                 println!("[SERVER]: Request rejected: {:?}", e);
                 FastcgiServerError::from(e)
             });
 
-            self.send_response(f(result)).await?
+            self.send_response(id, f(result)).await?
         } else {
             // TODO: log this.
             println!("[SERVER]: Request was aborted.");
@@ -45,22 +87,158 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Server<T> {
 
         Ok(())
     }
+
+    /// Receives the next request, or None if the client closed the connection between requests.
+    ///
+    /// Together with [`Server::respond`], this allows serving a connection in a loop:
+    ///
+    /// ```no_run
+    /// # use fastcgi::{response::Response, server::Server};
+    /// # async fn serve(socket: tokio::net::TcpStream) -> Result<(), fastcgi::FastcgiServerError> {
+    /// let mut server = Server::new(socket);
+    ///
+    /// while let Some(request) = server.next_request().await {
+    ///     let app_status = if request.is_ok() { 0 } else { 1 };
+    ///
+    ///     server
+    ///         .respond(Response::builder().app_status(app_status).build())
+    ///         .await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Aborted requests are skipped. A request which failed to be received is returned as an
+    /// error, which should be responded to as well. Errors which leave the connection unusable,
+    /// e.g. a corrupted header, are returned once, after which the loop ends.
+    pub async fn next_request(&mut self) -> Option<Result<Request, FastcgiServerError>> {
+        loop {
+            let result = Request::recv(&mut self.connection).await;
+            let id = self.connection.stream_id();
+
+            self.end_stream(result.is_err());
+
+            if let Err(e) = self.send_rejections().await {
+                return Some(Err(FastcgiServerError::from(e)));
+            }
+
+            let result = match result {
+                Ok(Some(request)) => Ok(request),
+                // The request was aborted.
+                Ok(None) => continue,
+                Err(ConnectionRecvError::UnexpectedEndOfInput) if id.is_none() => return None,
+                Err(e) if is_connection_error(&e) => {
+                    // The transport yields nothing after an error, which ends the loop.
+                    return Some(Err(FastcgiServerError::from(e)));
+                }
+                Err(e) => Err(FastcgiServerError::from(e)),
+            };
+
+            // The id is unknown if the request failed on its first frame.
+            self.current = Some(id.unwrap_or(1));
+
+            return Some(result);
+        }
+    }
+
+    /// Sends the response to the request which was last returned by [`Server::next_request`].
+    ///
+    /// The response is discarded if there's nothing to respond to, i.e. if the request was
+    /// already responded to, or if the connection failed.
+    pub async fn respond(&mut self, res: Response) -> Result<(), FastcgiServerError> {
+        let Some(id) = self.current.take() else {
+            return Ok(());
+        };
+
+        Ok(self.send_response(id, res).await?)
+    }
 }
 
-impl<T: AsyncRead + Unpin> Server<T> {
-    async fn recv_request(
+impl<T: AsyncRead + AsyncWrite + Unpin, D: Defragmenter> Server<T, D> {
+    /// Handles a request, and forwards each param to the sink as soon as it's received.
+    ///
+    /// This allows, e.g., a gateway to make routing decisions based on `REQUEST_METHOD` or
+    /// `REQUEST_URI` before the rest of the params and stdin have arrived. The request passed to
+    /// the handler still contains all the params.
+    pub async fn handle_request_streaming<S>(
         &mut self,
-    ) -> Result<Option<Request>, ConnectionRecvError<ParseRequestError>> {
-        let result = Request::recv(&mut self.connection).await;
+        sink: S,
+        f: impl Fn(Result<Request, FastcgiServerError>) -> Response,
+    ) -> Result<(), FastcgiServerError>
+    where
+        S: Sink<NameValuePair> + Unpin,
+        S::Error: std::error::Error + Send + Sync + 'static,
+    {
+        self.handle_request_by_role(|_| sink, f).await
+    }
 
-        self.connection.close_stream();
+    /// Handles a request, and forwards each param to the sink which `route` returns for the role
+    /// of the request.
+    ///
+    /// The role is known as soon as the `BeginRequest` is received, which allows dispatching to
+    /// Responder, Authorizer or Filter logic before the params and stdin have arrived.
+    pub async fn handle_request_by_role<S>(
+        &mut self,
+        route: impl FnOnce(Role) -> S,
+        f: impl Fn(Result<Request, FastcgiServerError>) -> Response,
+    ) -> Result<(), FastcgiServerError>
+    where
+        S: Sink<NameValuePair> + Unpin,
+        S::Error: std::error::Error + Send + Sync + 'static,
+    {
+        self.connection.open_stream(
+            server::State::from_config(self.connection.config()).with_params_streaming(),
+        );
+
+        let result = Request::recv_streaming(&mut self.connection, route).await;
 
-        result
+        self.handle_result(result, f).await
     }
 }
 
-impl<T: AsyncWrite + Unpin> Server<T> {
-    async fn send_response(&mut self, res: Response) -> Result<(), ConnectionSendError> {
-        res.send(&mut self.connection).await
+impl<T, D: Defragmenter> Server<T, D> {
+    /// Closes the stream of the request. The remaining frames of a failed request are skipped,
+    /// as they'd otherwise be parsed as the start of the next request.
+    fn end_stream(&mut self, failed: bool) {
+        if failed {
+            self.connection.discard_stream();
+        } else {
+            self.connection.close_stream();
+        }
     }
 }
+
+impl<T: AsyncWrite + Unpin, D: Defragmenter> Server<T, D> {
+    /// Rejects the requests which were received while another request was in progress, as
+    /// multiplexing isn't supported.
+    async fn send_rejections(&mut self) -> Result<(), ConnectionSendError> {
+        for id in self.connection.take_rejections() {
+            Response::send_rejection(id, ProtocolStatus::CantMpxConn, &mut self.connection).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send_response(&mut self, id: Id, res: Response) -> Result<(), ConnectionSendError> {
+        let max_response_size = self.connection.config().max_response_size;
+
+        if max_response_size.is_some_and(|max| res.payload_size() > max) {
+            // TODO: log this.
+            return Response::send_rejection(id, ProtocolStatus::Overloaded, &mut self.connection)
+                .await;
+        }
+
+        res.send(id, &mut self.connection).await
+    }
+}
+
+/// Returns true if the error leaves the connection unusable, in which case it must be closed
+/// instead of responded to. Only the requests of this connection are affected.
+fn is_connection_error(e: &ConnectionRecvError<ParseRequestError>) -> bool {
+    matches!(
+        e,
+        ConnectionRecvError::DecodeCodecError(_)
+            | ConnectionRecvError::StdIoError(_)
+            | ConnectionRecvError::UnexpectedEndOfInput
+    )
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "capture")]
+
+use std::net::Ipv4Addr;
+
+use futures::join;
+use tokio::net::{TcpListener, TcpStream};
+
+use fastcgi::{
+    capture::{CaptureTransport, ReplayTransport},
+    client::Client,
+    record::{ByteSlice, Params, Stdout},
+    request::{Request, Responder},
+    response::Response,
+    server::Server,
+};
+
+fn request(port: u16) -> Request {
+    let params = Params::builder::<Responder>()
+        .server_port(port)
+        .server_addr(Ipv4Addr::LOCALHOST.into());
+
+    Request::builder().params(params).build()
+}
+
+#[tokio::test]
+async fn captured_response_is_replayed() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        server
+            .handle_request(|_| {
+                Response::builder()
+                    .stdout(Stdout(ByteSlice::from_static(b"out").unwrap()))
+                    .app_status(0)
+                    .build()
+            })
+            .await
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let transport = CaptureTransport::new(stream);
+        let capture = transport.capture();
+
+        let mut client = Client::new(transport);
+        let response = client.send(request(addr.port())).await;
+
+        (response, capture)
+    };
+
+    let (result, (response, capture)) = join!(server, client);
+
+    result.unwrap();
+    response.unwrap();
+
+    // The captured request can be parsed back.
+    assert!(Request::parse(&capture.written()).unwrap().is_some());
+
+    // The captured response can be received again without a server.
+    let mut client = Client::new(ReplayTransport::new(capture.read()));
+    let response = client.send(request(addr.port())).await.unwrap();
+
+    assert_eq!(&response.get_stdout().as_ref().unwrap()[..], b"out");
+}
@@ -0,0 +1,153 @@
+use std::net::Ipv4Addr;
+
+use futures::join;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use fastcgi::{
+    client::Client,
+    conn::ConnectionRecvError,
+    record::Params,
+    request::{Request, Responder},
+    response::Response,
+    server::Server,
+    FastcgiClientError, FastcgiServerError,
+};
+
+/// The empty Stdin record which terminates the request.
+const END_OF_STDIN: [u8; 8] = [1, 5, 0, 1, 0, 0, 0, 0];
+
+#[tokio::test]
+async fn server_closes_connection_mid_response() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        // Read the entire request, so closing the socket doesn't reset the connection.
+        let mut request = Vec::new();
+        while !request.ends_with(&END_OF_STDIN) {
+            let mut buf = [0; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+        }
+
+        // Start a stdout stream, and close the connection before it ends.
+        socket
+            .write_all(&[1, 6, 0, 1, 0, 3, 5, 0, b'o', b'u', b't', 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+
+        client.send(Request::builder().params(params).build()).await
+    };
+
+    let (_, response) = join!(server, client);
+
+    assert!(matches!(
+        response,
+        Err(FastcgiClientError::Recv(
+            ConnectionRecvError::UnexpectedEndOfInput
+        ))
+    ));
+}
+
+/// Serves a connection until it's closed, and returns the number of errors received.
+async fn serve(socket: TcpStream) -> usize {
+    let mut server = Server::new(socket);
+    let mut errors = 0;
+
+    while let Some(request) = server.next_request().await {
+        errors += request.is_err() as usize;
+
+        server
+            .respond(Response::builder().app_status(0).build())
+            .await
+            .unwrap();
+    }
+
+    errors
+}
+
+#[tokio::test]
+async fn corrupted_connection_is_closed_without_affecting_others() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let accept = async {
+        let mut connections = Vec::new();
+
+        for _ in 0..2 {
+            let (socket, _) = listener.accept().await.unwrap();
+            connections.push(tokio::spawn(serve(socket)));
+        }
+
+        connections
+    };
+
+    let garbage = async {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        // A header with an unsupported version.
+        stream.write_all(&[2, 1, 0, 1, 0, 8, 0, 0]).await.unwrap();
+
+        // The server closes the connection without responding.
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        response
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+
+        client.send(Request::builder().params(params).build()).await
+    };
+
+    let (connections, garbage, response) = join!(accept, garbage, client);
+
+    assert!(garbage.is_empty());
+    assert_eq!(response.unwrap().get_app_status(), 0);
+
+    let mut errors = Vec::new();
+    for connection in connections {
+        errors.push(connection.await.unwrap());
+    }
+    errors.sort();
+    assert_eq!(errors, [0, 1]);
+}
+
+#[tokio::test]
+async fn handle_request_fails_on_corrupted_connection() {
+    let (mut client, socket) = tokio::io::duplex(1024);
+    let mut server = Server::new(socket);
+
+    client.write_all(&[2, 1, 0, 1, 0, 8, 0, 0]).await.unwrap();
+
+    let result = server
+        .handle_request(|_| panic!("the request can't be responded to"))
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(FastcgiServerError::Recv(
+            ConnectionRecvError::DecodeCodecError(_)
+        ))
+    ));
+}
@@ -0,0 +1,142 @@
+use std::{net::Ipv4Addr, time::Duration};
+
+use bytes::Bytes;
+use futures::join;
+use tokio::{
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+use fastcgi::{
+    client::Client,
+    conn::{Config, ConnectionRecvError, ConnectionSendError},
+    record::{ByteSlice, Params, Stdin},
+    request::{Request, Responder},
+    response::Response,
+    FastcgiClientError,
+};
+
+/// Sends a request to a server which writes `response`, and then stalls.
+async fn send_to_stalling_server(
+    response: &[u8],
+    config: Config,
+) -> Result<Response, FastcgiClientError> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        let mut buf = [0; 1024];
+        let _ = socket.read(&mut buf).await.unwrap();
+
+        socket.write_all(response).await.unwrap();
+        time::sleep(Duration::from_millis(500)).await;
+
+        socket
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::with_config(stream, config);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+
+        client.send(Request::builder().params(params).build()).await
+    };
+
+    let (_socket, response) = join!(server, client);
+
+    response
+}
+
+#[tokio::test]
+async fn request_exceeding_deadline_is_aborted() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Accepts the connection, but never responds.
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        time::sleep(Duration::from_millis(500)).await;
+
+        socket
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+        let request = Request::builder()
+            .params(params)
+            .deadline(Duration::from_millis(50))
+            .build();
+
+        client.send(request).await
+    };
+
+    let (_socket, response) = join!(server, client);
+
+    assert!(matches!(
+        response,
+        Err(FastcgiClientError::DeadlineExceeded)
+    ));
+}
+
+#[tokio::test]
+async fn first_frame_timeout() {
+    let config = Config::new().first_frame_timeout(Duration::from_millis(50));
+
+    let response = send_to_stalling_server(&[], config).await;
+
+    assert!(matches!(
+        response,
+        Err(FastcgiClientError::Recv(
+            ConnectionRecvError::FirstFrameTimeout
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn idle_timeout() {
+    let config = Config::new()
+        .first_frame_timeout(Duration::from_millis(50))
+        .idle_timeout(Duration::from_millis(50));
+
+    // The start of a stdout stream, which is never ended.
+    let stdout = [1, 6, 0, 1, 0, 3, 5, 0, b'o', b'u', b't', 0, 0, 0, 0, 0];
+    let response = send_to_stalling_server(&stdout, config).await;
+
+    assert!(matches!(
+        response,
+        Err(FastcgiClientError::Recv(ConnectionRecvError::IdleTimeout))
+    ));
+}
+
+#[tokio::test]
+async fn write_timeout() {
+    // The peer never reads, so the small buffer of the pipe fills up.
+    let (stream, _peer) = io::duplex(1024);
+
+    let config = Config::new().write_timeout(Duration::from_millis(50));
+    let mut client = Client::with_config(stream, config);
+
+    let params = Params::builder::<Responder>()
+        .server_port(80)
+        .server_addr(Ipv4Addr::LOCALHOST.into());
+    let stdin = Stdin(ByteSlice::new(Bytes::from(vec![0; 200_000])).unwrap());
+    let request = Request::builder().params(params).stdin(stdin).build();
+
+    let response = client.send(request).await;
+
+    assert!(matches!(
+        response,
+        Err(FastcgiClientError::Send(ConnectionSendError::WriteTimeout))
+    ));
+}
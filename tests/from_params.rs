@@ -0,0 +1,59 @@
+#![cfg(feature = "derive")]
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use fastcgi::{
+    record::{FromParams, FromParamsError, NameValuePair, Params},
+    request::Responder,
+};
+
+#[derive(Debug, PartialEq, FromParams)]
+struct CgiVars {
+    #[param("SERVER_PORT")]
+    port: u16,
+    #[param("SERVER_ADDR")]
+    addr: IpAddr,
+    #[param("SERVER_PROTOCOL")]
+    protocol: Option<String>,
+}
+
+fn params() -> Params {
+    Params::builder::<Responder>()
+        .server_port(80)
+        .server_addr(Ipv4Addr::LOCALHOST.into())
+        .build()
+}
+
+#[test]
+fn params_into_typed_struct() {
+    let cgi: CgiVars = params().parse().unwrap();
+
+    assert_eq!(
+        cgi,
+        CgiVars {
+            port: 80,
+            addr: Ipv4Addr::LOCALHOST.into(),
+            protocol: None,
+        }
+    );
+}
+
+#[test]
+fn missing_required_param() {
+    let params = Params::builder::<Responder>().server_port(80).build();
+
+    assert_eq!(
+        params.parse::<CgiVars>(),
+        Err(FromParamsError::MissingParam("SERVER_ADDR"))
+    );
+}
+
+#[test]
+fn invalid_param() {
+    let params = params().insert_nvp(NameValuePair::new("SERVER_PROTOCOL", &b"\xff"[..]).unwrap());
+
+    assert_eq!(
+        params.parse::<CgiVars>(),
+        Err(FromParamsError::InvalidParam("SERVER_PROTOCOL"))
+    );
+}
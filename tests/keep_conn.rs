@@ -0,0 +1,174 @@
+use std::net::Ipv4Addr;
+
+use bytes::Bytes;
+use futures::join;
+use tokio::net::{TcpListener, TcpStream};
+
+use fastcgi::{
+    client::Client,
+    conn::Config,
+    record::{ByteSlice, Params, Stdin, Stdout},
+    request::{Request, Responder},
+    response::Response,
+    server::Server,
+};
+
+#[tokio::test]
+async fn sequential_requests_on_one_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        for body in [&b"first"[..], &b"second"[..]] {
+            server
+                .handle_request(|req| {
+                    assert!(req.unwrap().get_keep_conn());
+
+                    Response::builder()
+                        .stdout(Stdout(ByteSlice::new(Bytes::from(body)).unwrap()))
+                        .app_status(0)
+                        .build()
+                })
+                .await?;
+        }
+
+        Ok::<_, fastcgi::FastcgiServerError>(())
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let mut bodies = Vec::new();
+
+        for _ in 0..2 {
+            let params = Params::builder::<Responder>()
+                .server_port(addr.port())
+                .server_addr(Ipv4Addr::LOCALHOST.into());
+            let request = Request::builder().params(params).keep_conn().build();
+
+            let response = client.send(request).await.unwrap();
+            bodies.push(response.get_stdout().as_ref().unwrap().to_vec());
+        }
+
+        bodies
+    };
+
+    let (result, bodies) = join!(server, client);
+
+    result.unwrap();
+    assert_eq!(bodies, [&b"first"[..], &b"second"[..]]);
+}
+
+#[tokio::test]
+async fn requests_are_served_in_a_loop() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        let mut served = 0;
+
+        while let Some(request) = server.next_request().await {
+            let port = request
+                .unwrap()
+                .get_params()
+                .get("SERVER_PORT")
+                .unwrap()
+                .to_vec();
+
+            server
+                .respond(
+                    Response::builder()
+                        .stdout(Stdout(ByteSlice::new(Bytes::from(port)).unwrap()))
+                        .app_status(0)
+                        .build(),
+                )
+                .await?;
+
+            served += 1;
+        }
+
+        Ok::<_, fastcgi::FastcgiServerError>(served)
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        for _ in 0..3 {
+            let params = Params::builder::<Responder>()
+                .server_port(addr.port())
+                .server_addr(Ipv4Addr::LOCALHOST.into());
+            let request = Request::builder().params(params).keep_conn().build();
+
+            let response = client.send(request).await.unwrap();
+            assert_eq!(
+                response.get_stdout().as_ref().unwrap().to_vec(),
+                addr.port().to_string().into_bytes()
+            );
+        }
+
+        // Closes the connection.
+    };
+
+    let (served, ()) = join!(server, client);
+
+    assert_eq!(served.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn rejected_request_does_not_affect_the_next() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::with_config(socket, Config::new().max_content_length(4));
+
+        for _ in 0..2 {
+            server
+                .handle_request(|req| {
+                    let app_status = if req.is_ok() { 0 } else { 1 };
+
+                    Response::builder().app_status(app_status).build()
+                })
+                .await?;
+        }
+
+        Ok::<_, fastcgi::FastcgiServerError>(())
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let mut app_statuses = Vec::new();
+
+        for body in [&b"too large"[..], &b"ok"[..]] {
+            let params = Params::builder::<Responder>()
+                .server_port(addr.port())
+                .content_length(body.len() as u64);
+            let request = Request::builder()
+                .params(params)
+                .stdin(Stdin(ByteSlice::new(Bytes::from(body)).unwrap()))
+                .keep_conn()
+                .build();
+
+            let response = client.send(request).await.unwrap();
+            app_statuses.push(response.get_app_status());
+        }
+
+        app_statuses
+    };
+
+    let (result, app_statuses) = join!(server, client);
+
+    result.unwrap();
+    assert_eq!(app_statuses, [1, 0]);
+}
@@ -0,0 +1,62 @@
+use std::{
+    net::Ipv4Addr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bytes::Bytes;
+use futures::join;
+use tokio::net::{TcpListener, TcpStream};
+
+use fastcgi::{
+    client::Client,
+    record::{ByteSlice, Params, Stdin},
+    request::{Request, Responder},
+    response::Response,
+    server::Server,
+};
+
+#[tokio::test]
+async fn large_request_is_flushed_completely() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Spans many frames, and exceeds the socket buffers.
+    let body = Bytes::from(vec![b'a'; 4 * 1024 * 1024]);
+    let received = AtomicUsize::new(0);
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        server
+            .handle_request(|req| {
+                let stdin = req.unwrap().get_stdin().as_ref().map_or(0, |stdin| {
+                    let bytes: &Bytes = stdin.as_ref();
+                    bytes.len()
+                });
+                received.store(stdin, Ordering::SeqCst);
+
+                Response::builder().app_status(0).build()
+            })
+            .await
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+        let stdin = Stdin(ByteSlice::new(body.clone()).unwrap());
+        let request = Request::builder().params(params).stdin(stdin).build();
+
+        client.send(request).await
+    };
+
+    let (result, response) = join!(server, client);
+
+    result.unwrap();
+    response.unwrap();
+    assert_eq!(received.load(Ordering::SeqCst), body.len());
+}
@@ -0,0 +1,186 @@
+use std::{
+    net::Ipv4Addr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures::join;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use fastcgi::{
+    client::Client,
+    codec::Frame,
+    conn::{Config, RecordHandler},
+    record::{DecodeFrame, GetValuesResult, Params, RecordType, Standard},
+    request::{Request, Responder},
+    response::Response,
+    FastcgiClientError,
+};
+
+/// The empty Stdin record which terminates the request.
+const END_OF_STDIN: [u8; 8] = [1, 5, 0, 1, 0, 0, 0, 0];
+
+/// Returns a handler which checks that it receives a GetValuesResult, and the number of records
+/// it handled.
+fn get_values_result_handler() -> (RecordHandler, Arc<AtomicUsize>) {
+    let handled = Arc::new(AtomicUsize::new(0));
+    let counter = handled.clone();
+
+    let handler = Arc::new(move |frame: &Frame| {
+        let (id, record_type, payload) = frame.as_parts();
+
+        assert_eq!(id, 0);
+        assert_eq!(record_type, RecordType::from(Standard::GetValuesResult));
+
+        let result = GetValuesResult::decode_frame(payload.clone()).unwrap();
+        let pairs = result.iter_str().collect::<Vec<_>>();
+        assert_eq!(pairs, [("FCGI_MPXS_CONNS".into(), "0".into())]);
+
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    (handler, handled)
+}
+
+/// Reads a request until its stdin ends.
+async fn read_request(socket: &mut TcpStream) {
+    let mut request = Vec::new();
+
+    while !request.ends_with(&END_OF_STDIN) {
+        let mut buf = [0; 1024];
+        let n = socket.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+    }
+}
+
+/// Sends a request to a server which interleaves a GetValuesResult with its response.
+async fn send_with_interleaved_record(config: Config) -> Result<Response, FastcgiClientError> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        read_request(&mut socket).await;
+
+        let mut get_values_result = vec![1, 10, 0, 0, 0, 18, 0, 0, 15, 1];
+        get_values_result.extend_from_slice(b"FCGI_MPXS_CONNS0");
+
+        let response = [
+            &[1, 6, 0, 1, 0, 4, 0, 0][..],
+            b"out ",
+            &get_values_result,
+            &[1, 6, 0, 1, 0, 4, 0, 0],
+            b"more",
+            &[1, 6, 0, 1, 0, 0, 0, 0],
+            &[1, 3, 0, 1, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ]
+        .concat();
+
+        socket.write_all(&response).await.unwrap();
+
+        socket
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::with_config(stream, config);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+
+        client.send(Request::builder().params(params).build()).await
+    };
+
+    let (_socket, response) = join!(server, client);
+
+    response
+}
+
+#[tokio::test]
+async fn management_record_interleaved_with_stdout() {
+    let (handler, handled) = get_values_result_handler();
+    let config = Config::new().management_record_handler(handler);
+
+    let response = send_with_interleaved_record(config).await.unwrap();
+
+    assert_eq!(
+        response.get_stdout().as_ref().unwrap().0.bytes(),
+        &b"out more"[..]
+    );
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn unsolicited_management_record_is_dropped() {
+    let response = send_with_interleaved_record(Config::new()).await.unwrap();
+
+    assert_eq!(
+        response.get_stdout().as_ref().unwrap().0.bytes(),
+        &b"out more"[..]
+    );
+}
+
+#[tokio::test]
+async fn trailers_are_read_after_the_response() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (mut socket, _) = listener.accept().await.unwrap();
+
+        read_request(&mut socket).await;
+
+        let response = [
+            &[1, 6, 0, 1, 0, 0, 0, 0][..],
+            &[1, 3, 0, 1, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ]
+        .concat();
+
+        socket.write_all(&response).await.unwrap();
+
+        // A vendor record with trailing metadata, after the response has ended.
+        socket.write_all(&[1, 20, 0, 0, 0, 4, 0, 0]).await.unwrap();
+        socket.write_all(b"meta").await.unwrap();
+
+        socket
+    };
+
+    let client = async {
+        let trailers = Arc::new(Mutex::new(Vec::new()));
+        let collected = trailers.clone();
+
+        let handler = Arc::new(move |frame: &Frame| {
+            let (_, _, payload) = frame.as_parts();
+            collected.lock().unwrap().push(payload.to_vec());
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client =
+            Client::with_config(stream, Config::new().management_record_handler(handler));
+
+        let params = Params::builder::<Responder>().server_port(addr.port());
+        let request = Request::builder().params(params).keep_conn().build();
+
+        client.send(request).await.unwrap();
+
+        let received = client
+            .recv_trailers(Duration::from_millis(100))
+            .await
+            .unwrap();
+
+        (received, trailers)
+    };
+
+    let (_socket, (received, trailers)) = join!(server, client);
+
+    assert_eq!(received, 1);
+    assert_eq!(*trailers.lock().unwrap(), [b"meta".to_vec()]);
+}
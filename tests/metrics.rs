@@ -0,0 +1,89 @@
+use std::net::Ipv4Addr;
+
+use bytes::Bytes;
+use futures::join;
+use tokio::net::{TcpListener, TcpStream};
+
+use fastcgi::{
+    client::Client,
+    conn::Config,
+    record::{ByteSlice, Params, Stdout},
+    request::{Request, Responder},
+    response::{RequestMetrics, Response},
+    server::Server,
+};
+
+async fn send_twice(config: Config) -> Vec<Option<RequestMetrics>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        for _ in 0..2 {
+            server
+                .handle_request(|_| {
+                    Response::builder()
+                        .stdout(Stdout(ByteSlice::new(Bytes::from("hello")).unwrap()))
+                        .app_status(0)
+                        .build()
+                })
+                .await?;
+        }
+
+        Ok::<_, fastcgi::FastcgiServerError>(())
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::with_config(stream, config);
+
+        let mut metrics = Vec::new();
+
+        for _ in 0..2 {
+            let params = Params::builder::<Responder>()
+                .server_port(addr.port())
+                .server_addr(Ipv4Addr::LOCALHOST.into());
+            let request = Request::builder().params(params).keep_conn().build();
+
+            let response = client.send(request).await.unwrap();
+            metrics.push(response.get_metrics());
+        }
+
+        metrics
+    };
+
+    let (result, metrics) = join!(server, client);
+
+    result.unwrap();
+    metrics
+}
+
+#[tokio::test]
+async fn metrics_are_disabled_by_default() {
+    let metrics = send_twice(Config::default()).await;
+
+    assert_eq!(metrics, [None, None]);
+}
+
+#[tokio::test]
+async fn metrics_are_counted_per_request() {
+    let metrics = send_twice(Config::default().request_metrics(true)).await;
+
+    let first = metrics[0].unwrap();
+    let second = metrics[1].unwrap();
+
+    // Stdout, the ends of stdout and stderr, and EndRequest.
+    assert_eq!(first.received.frames, 4);
+    assert!(first.received.bytes >= 4 * 8 + 5 + 8);
+
+    // BeginRequest, at least one params frame, the end of params, and the end of stdin.
+    assert!(first.sent.frames >= 4);
+    assert!(first.sent.bytes >= first.sent.frames * 8);
+
+    // Only stdout is reassembled, as the stderr stream is empty.
+    assert_eq!(first.peak_defrag_size, 5);
+
+    assert_eq!(first, second);
+}
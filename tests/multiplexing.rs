@@ -0,0 +1,97 @@
+use bytes::BytesMut;
+use futures::join;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tokio_util::codec::Decoder;
+
+use fastcgi::{
+    codec::FastCgiCodec,
+    record::{EndRequest, ProtocolStatus, RecordType, Standard},
+    response::Response,
+    server::Server,
+};
+
+fn frame(id: u8, record_type: Standard, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![1, record_type as u8, 0, id, 0, payload.len() as u8, 0, 0];
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn begin_responder(id: u8) -> Vec<u8> {
+    frame(id, Standard::BeginRequest, &[0, 1, 0, 0, 0, 0, 0, 0])
+}
+
+#[tokio::test]
+async fn concurrent_request_is_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        server
+            .handle_request(|req| {
+                assert_eq!(req.unwrap().get_id(), 1);
+
+                Response::builder().app_status(0).build()
+            })
+            .await
+    };
+
+    let client = async {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        // The frames of the second request are interleaved with those of the first.
+        let request = [
+            begin_responder(1),
+            begin_responder(2),
+            frame(2, Standard::Params, b"\x01\x01ab"),
+            frame(1, Standard::Params, b"\x01\x01cd"),
+            frame(1, Standard::Params, b""),
+            frame(2, Standard::Params, b""),
+            frame(1, Standard::Stdin, b""),
+            frame(2, Standard::Stdin, b""),
+        ]
+        .concat();
+
+        stream.write_all(&request).await.unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let mut src = BytesMut::from(&response[..]);
+        let mut codec = FastCgiCodec::new();
+        let mut frames = Vec::new();
+        while let Some(frame) = codec.decode(&mut src).unwrap() {
+            frames.push(frame.into_parts());
+        }
+
+        frames
+    };
+
+    let (result, frames) = join!(server, client);
+
+    result.unwrap();
+
+    let end_requests = frames
+        .into_iter()
+        .filter(|(_, record_type, _)| *record_type == RecordType::from(Standard::EndRequest))
+        .map(|(id, _, payload)| {
+            (
+                id,
+                EndRequest::decode(payload).unwrap().get_protocol_status(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        end_requests,
+        [
+            (2, ProtocolStatus::CantMpxConn),
+            (1, ProtocolStatus::RequestComplete),
+        ]
+    );
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "unstable")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bytes::BytesMut;
+use futures::{join, SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use fastcgi::{
+    client::Client,
+    codec::FrameTransport,
+    conn::{ConnectionRecvError, ParseRequestError},
+    record::{ByteSlice, RecordType, Standard, Stdout},
+    request::Request,
+    response::Response,
+    server::Server,
+    FastcgiServerError,
+};
+
+#[tokio::test]
+async fn server_rejects_unexpected_raw_frame() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let rejected = AtomicBool::new(false);
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        server
+            .handle_request(|req| {
+                if let Err(FastcgiServerError::Recv(ConnectionRecvError::ParserError(
+                    ParseRequestError::UnexpectedRecordType(record_type),
+                ))) = req
+                {
+                    rejected.store(record_type == Standard::Stdout, Ordering::SeqCst);
+                }
+
+                Response::builder().app_status(1).build()
+            })
+            .await
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        // A request must start with a BeginRequest record.
+        client
+            .send_raw(
+                1,
+                RecordType::Standard(Standard::Stdout),
+                BytesMut::from(&b"unexpected"[..]),
+            )
+            .await
+            .unwrap();
+
+        client
+    };
+
+    let (result, _client) = join!(server, client);
+
+    result.unwrap();
+    assert!(rejected.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn frames_are_relayed_over_frame_transport() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        server
+            .handle_request(|_| {
+                Response::builder()
+                    .stdout(Stdout(ByteSlice::from_static(b"out").unwrap()))
+                    .app_status(0)
+                    .build()
+            })
+            .await
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut transport = FrameTransport::new(stream);
+
+        for frame in Request::get("/index.php").into_frames().unwrap() {
+            transport.feed(frame).await.unwrap();
+        }
+        transport.flush().await.unwrap();
+
+        let mut record_types = Vec::new();
+
+        while let Some(frame) = transport.next().await {
+            let (_, record_type, _) = frame.unwrap().into_parts();
+            record_types.push(record_type);
+
+            if record_type == Standard::EndRequest {
+                break;
+            }
+        }
+
+        record_types
+    };
+
+    let (result, record_types) = join!(server, client);
+
+    result.unwrap();
+    assert_eq!(
+        record_types,
+        [
+            Standard::Stdout,
+            Standard::Stdout,
+            Standard::Stderr,
+            Standard::EndRequest
+        ]
+    );
+}
@@ -0,0 +1,99 @@
+use std::net::Ipv4Addr;
+
+use bytes::Bytes;
+use futures::join;
+use tokio::net::{TcpListener, TcpStream};
+
+use fastcgi::{
+    client::Client,
+    conn::{Config, ConnectionRecvError, ParseRequestError},
+    record::{ByteSlice, Params, ProtocolStatus, Stdout},
+    request::{Request, Responder},
+    response::Response,
+    server::Server,
+    FastcgiClientError, FastcgiServerError,
+};
+
+#[tokio::test]
+async fn oversized_response_is_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::with_config(socket, Config::new().max_response_size(4));
+
+        server
+            .handle_request(|_| {
+                let stdout = ByteSlice::new(Bytes::from_static(b"too large")).unwrap();
+
+                Response::builder()
+                    .stdout(Stdout(stdout))
+                    .app_status(0)
+                    .build()
+            })
+            .await
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+
+        client.send(Request::builder().params(params).build()).await
+    };
+
+    let (result, response) = join!(server, client);
+
+    result.unwrap();
+    assert!(matches!(
+        response,
+        Err(FastcgiClientError::Recv(
+            ConnectionRecvError::ProtocolStatus(ProtocolStatus::Overloaded)
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn declared_content_length_is_rejected_before_stdin() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::with_config(socket, Config::new().max_content_length(4));
+
+        let result = server
+            .handle_request(|req| {
+                let rejected = matches!(
+                    req,
+                    Err(FastcgiServerError::Recv(ConnectionRecvError::ParserError(
+                        ParseRequestError::ExceededMaximumContentLength(9)
+                    )))
+                );
+
+                Response::builder().app_status(rejected as u32).build()
+            })
+            .await;
+
+        // Keep the connection open until the client has received the response.
+        (result, server)
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        client
+            .send(Request::post("/var/www/form.php", "too large"))
+            .await
+    };
+
+    let ((result, _server), response) = join!(server, client);
+
+    result.unwrap();
+    assert_eq!(response.unwrap().get_app_status(), 1);
+}
@@ -0,0 +1,171 @@
+use std::{net::Ipv4Addr, time::SystemTime};
+
+use bytes::Bytes;
+use futures::{channel::mpsc, join, SinkExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use fastcgi::{
+    client::Client,
+    record::{ByteSlice, Data, NameValuePair, Params, Role, Stdin, Stdout},
+    request::{Filter, Request, Responder, SentLengths},
+    response::Response,
+    server::Server,
+};
+
+#[tokio::test]
+async fn stdout_is_streamed_into_sink() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Spans multiple frames.
+    let body = Bytes::from(vec![b'a'; 200_000]);
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        server
+            .handle_request(|_| {
+                Response::builder()
+                    .stdout(Stdout(ByteSlice::new(body.clone()).unwrap()))
+                    .app_status(0)
+                    .build()
+            })
+            .await
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+        let request = Request::builder().params(params).build();
+
+        let mut chunks: Vec<Bytes> = Vec::new();
+        let response = client.send_streaming(request, &mut chunks).await;
+
+        (response, chunks)
+    };
+
+    let (result, (response, chunks)) = join!(server, client);
+
+    result.unwrap();
+
+    let response = response.unwrap();
+    assert!(response.get_stdout().is_none());
+    assert!(chunks.len() > 1);
+    assert_eq!(chunks.concat(), body);
+}
+
+#[tokio::test]
+async fn stdin_is_streamed_from_channel() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        // Echo stdin back as stdout.
+        server
+            .handle_request(|req| {
+                let stdin = req.unwrap().get_stdin().clone().unwrap();
+
+                Response::builder()
+                    .stdout(Stdout(stdin.0))
+                    .app_status(0)
+                    .build()
+            })
+            .await
+    };
+
+    let (mut tx, rx) = mpsc::channel(1);
+
+    let producer = async move {
+        for chunk in [&b"first "[..], b"", b"second"] {
+            tx.send(Bytes::from(chunk)).await.unwrap();
+        }
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let params = Params::builder::<Responder>()
+            .server_port(addr.port())
+            .server_addr(Ipv4Addr::LOCALHOST.into());
+        let request = Request::builder()
+            .params(params)
+            .stdin(Stdin(ByteSlice::new(Bytes::from("head: ")).unwrap()))
+            .build();
+
+        client.send_with_stdin(request, rx).await
+    };
+
+    let (result, (), response) = join!(server, producer, client);
+
+    result.unwrap();
+
+    let response = response.unwrap();
+    assert_eq!(
+        &response.get_stdout().as_ref().unwrap()[..],
+        b"head: first second"
+    );
+    assert_eq!(
+        response.get_sent(),
+        Some(SentLengths { stdin: 18, data: 0 })
+    );
+}
+
+#[tokio::test]
+async fn role_is_known_before_params() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = async {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut server = Server::new(socket);
+
+        let mut role = None;
+        let mut params: Vec<NameValuePair> = Vec::new();
+
+        let result = server
+            .handle_request_by_role(
+                |r| {
+                    role = Some(r);
+                    &mut params
+                },
+                |req| {
+                    assert!(req.unwrap().get_data().is_some());
+
+                    Response::builder().app_status(0).build()
+                },
+            )
+            .await;
+
+        (result, role, params)
+    };
+
+    let client = async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut client = Client::new(stream);
+
+        let params = Params::builder::<Filter>().server_port(addr.port());
+        let request = Request::builder()
+            .params(params)
+            .data(Data::from("data"), SystemTime::UNIX_EPOCH)
+            .build();
+
+        client.send(request).await
+    };
+
+    let ((result, role, params), response) = join!(server, client);
+
+    result.unwrap();
+
+    assert_eq!(role, Some(Role::Filter));
+    assert!(!params.is_empty());
+    assert_eq!(response.unwrap().get_app_status(), 0);
+}